@@ -0,0 +1,181 @@
+// MockProver, keygen, proving and verification times of the example chips.
+//
+// $ cargo bench
+// $ cargo bench -- fibonacci_rows
+//
+// After the run, the mean of each benchmark (in ns) is collected from the criterion reports
+// into `target/criterion/summary.json`, to track regressions across commits.
+
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+
+use criterion::{criterion_group, BenchmarkId, Criterion};
+use ff::PrimeFieldBits;
+use halo2_examples::{
+    fibonacci::{
+        compare::{self, ColumnCircuit, RowsCircuit},
+        example5, example6,
+    },
+    prover::Setup,
+    range_check::{
+        chip::{RangeCheckChip, RangeCheckConfig},
+        decompose_range_check::DecomposeRangeCheckCircuit,
+    },
+    stats::min_k,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+/// One value checked by `RangeCheckChip`, whose strategy depends on `num_bits`.
+#[derive(Clone)]
+struct ChipRangeCheck<F: PrimeFieldBits> {
+    value: Value<F>,
+    num_bits: usize,
+}
+
+const MAX_POLY_BITS: usize = 2;
+const LOOKUP_BITS: usize = 8;
+
+impl<F: PrimeFieldBits> Circuit<F> for ChipRangeCheck<F> {
+    type Config = RangeCheckConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckChip::configure(meta, value, MAX_POLY_BITS, LOOKUP_BITS)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = RangeCheckChip::construct(config);
+        chip.load_table(&mut layouter)?;
+        chip.assign(layouter.namespace(|| "range check"), self.value, self.num_bits)?;
+        Ok(())
+    }
+}
+
+/// Benchmarks `MockProver`, keygen, proving and verification of `circuit` in `group`, with
+/// `parameter` as the id of the circuit, e.g. its length.
+fn bench_circuit<C: Circuit<Fp> + Clone>(
+    c: &mut Criterion,
+    group: &str,
+    parameter: impl Display,
+    circuit: C,
+    instances: Vec<Vec<Fp>>,
+) {
+    let k = min_k(&circuit).unwrap();
+    let setup = Setup::new(k, &circuit).unwrap();
+    let proof = setup.prove(circuit.clone(), &instances).unwrap();
+    assert!(setup.verify(&proof, &instances).is_accepted());
+
+    let mut group = c.benchmark_group(group);
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::new("mock", &parameter), |b| {
+        b.iter(|| {
+            MockProver::run(k, &circuit, instances.clone())
+                .unwrap()
+                .assert_satisfied()
+        })
+    });
+    group.bench_function(BenchmarkId::new("keygen", &parameter), |b| {
+        b.iter(|| Setup::new(k, &circuit).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("prove", &parameter), |b| {
+        b.iter(|| setup.prove(circuit.clone(), &instances).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("verify", &parameter), |b| {
+        b.iter(|| setup.verify(&proof, &instances))
+    });
+    group.finish();
+}
+
+fn fibonacci(c: &mut Criterion) {
+    for n in [16, 128, 1024] {
+        let instances = compare::instances(n);
+        bench_circuit(c, "fibonacci_rows", n, RowsCircuit::new(n), instances.clone());
+        bench_circuit(c, "fibonacci_column", n, ColumnCircuit::new(n), instances.clone());
+        let matrix = example5::FibonacciCircuit::new(n);
+        bench_circuit(c, "fibonacci_matrix", n, matrix, instances);
+        let doubling = example6::FibonacciCircuit::new(n as u64);
+        let instances = example6::instances(n as u64, Fp::from(1), Fp::from(1));
+        bench_circuit(c, "fibonacci_doubling", n, doubling, instances);
+    }
+}
+
+fn range_check(c: &mut Criterion) {
+    // polynomial, lookup, then running sums of 2, 4 and 8 windows
+    for num_bits in [2, 8, 16, 32, 64] {
+        let circuit = ChipRangeCheck {
+            value: Value::known(Fp::from(u64::MAX >> (64 - num_bits))),
+            num_bits,
+        };
+        bench_circuit(c, "range_check_chip", num_bits, circuit, vec![]);
+    }
+
+    for num_bits in [8, 32, 64] {
+        let value = u128::from(u64::MAX >> (64 - num_bits));
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(value, num_bits);
+        bench_circuit(c, "decompose_range_check", num_bits, circuit, vec![]);
+    }
+}
+
+/// Collects the mean of every benchmark from the `estimates.json` files of criterion.
+fn summarize(criterion_dir: &Path) -> std::io::Result<()> {
+    fn visit(dir: &Path, root: &Path, means: &mut BTreeMap<String, f64>) -> std::io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let estimates = path.join("new").join("estimates.json");
+            if estimates.exists() {
+                let json: serde_json::Value = serde_json::from_slice(&fs::read(&estimates)?)?;
+                if let Some(mean) = json["mean"]["point_estimate"].as_f64() {
+                    let id = path.strip_prefix(root).unwrap().to_string_lossy().into_owned();
+                    means.insert(id, mean);
+                }
+            } else {
+                visit(&path, root, means)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut means = BTreeMap::new();
+    visit(criterion_dir, criterion_dir, &mut means)?;
+    fs::write(
+        criterion_dir.join("summary.json"),
+        serde_json::to_string_pretty(&means)?,
+    )
+}
+
+criterion_group!(benches, fibonacci, range_check);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+
+    // where criterion writes its reports
+    let criterion_dir = std::env::var_os("CRITERION_HOME")
+        .map(Into::into)
+        .unwrap_or_else(|| {
+            let target = std::env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+            Path::new(&target).join("criterion")
+        });
+    if criterion_dir.exists() {
+        summarize(&criterion_dir).expect("writing the summary");
+    }
+}
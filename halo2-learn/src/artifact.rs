@@ -0,0 +1,398 @@
+//! On-disk formats for params, proofs, public inputs and verifying key fingerprints, so that
+//! params can be cached and proofs checked later.
+//!
+//! Each file is a one-line JSON envelope followed by its payload:
+//!
+//! ```text
+//! {"version":2,"kind":"proof","circuit":"fib1","k":4,"field":"pasta::Fp","backend":"ipa-pasta","vk":"0x1a2b..."}
+//! <proof transcript>
+//! ```
+//!
+//! | kind             | payload                                          |
+//! |------------------|--------------------------------------------------|
+//! | `params`         | `Params::write`                                  |
+//! | `vk_fingerprint` | [`vk_fingerprint`] of the verifying key, in hex  |
+//! | `proof`          | the transcript bytes of the proof                |
+//! | `instances`      | a JSON array of instance columns of hex values   |
+//!
+//! Keys are not cached: zcash halo2 can't serialize them, so [`load_setup`] regenerates
+//! them from cached params in each run, and [`check_vk_fingerprint`] rejects a circuit whose
+//! key differs from the recorded one. The `vk` of the envelopes is the same fingerprint: a
+//! proof is only loaded for the key it was made with.
+
+use std::{fmt, fs, io, path::Path};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, Error, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bWrite, Challenge255, EncodedChallenge, Transcript},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prover::{Proof, Setup},
+    witness::to_hex,
+};
+
+/// Bumped on any change of the envelope or of a payload.
+pub const FORMAT_VERSION: u32 = 2;
+pub const FIELD: &str = "pasta::Fp";
+pub const BACKEND: &str = "ipa-pasta";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Params,
+    VkFingerprint,
+    Proof,
+    Instances,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    pub kind: Kind,
+    /// The name of the circuit, e.g. `fib1`; params are shared by every circuit.
+    pub circuit: Option<String>,
+    pub k: u32,
+    pub field: String,
+    pub backend: String,
+    /// The fingerprint of the verifying key the artifact belongs to.
+    pub vk: Option<String>,
+}
+
+impl Envelope {
+    pub fn new(kind: Kind, k: u32) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            kind,
+            circuit: None,
+            k,
+            field: FIELD.to_string(),
+            backend: BACKEND.to_string(),
+            vk: None,
+        }
+    }
+
+    // An envelope for `setup` and the circuit named `circuit`.
+    fn for_setup(kind: Kind, circuit: &str, setup: &Setup) -> Self {
+        Self {
+            circuit: Some(circuit.to_string()),
+            vk: Some(vk_fingerprint(setup.vk())),
+            ..Self::new(kind, setup.k)
+        }
+    }
+
+    // Rejects an artifact of another circuit or key than `expected`.
+    fn check(&self, expected: &Envelope) -> Result<(), ArtifactError> {
+        check("circuit", &expected.circuit, &self.circuit)?;
+        check("k", &expected.k, &self.k)?;
+        check("vk", &expected.vk, &self.vk)
+    }
+}
+
+#[derive(Debug)]
+pub enum ArtifactError {
+    Io(io::Error),
+    Format(String),
+    /// The artifact is not the one expected, e.g. a proof of another circuit.
+    Mismatch {
+        field: &'static str,
+        expected: String,
+        found: String,
+    },
+    Synthesis(Error),
+}
+
+impl From<io::Error> for ArtifactError {
+    fn from(e: io::Error) -> Self {
+        ArtifactError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ArtifactError {
+    fn from(e: serde_json::Error) -> Self {
+        ArtifactError::Format(e.to_string())
+    }
+}
+
+impl From<Error> for ArtifactError {
+    fn from(e: Error) -> Self {
+        ArtifactError::Synthesis(e)
+    }
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactError::Io(e) => write!(f, "{}", e),
+            ArtifactError::Format(e) => write!(f, "invalid artifact: {}", e),
+            ArtifactError::Mismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "{} mismatch: expected {}, found {}", field, expected, found),
+            ArtifactError::Synthesis(e) => write!(f, "keygen failed: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError {}
+
+fn check<T: PartialEq + fmt::Debug>(
+    field: &'static str,
+    expected: &T,
+    found: &T,
+) -> Result<(), ArtifactError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ArtifactError::Mismatch {
+            field,
+            expected: format!("{:?}", expected),
+            found: format!("{:?}", found),
+        })
+    }
+}
+
+/// Writes `envelope` and `payload` to `path`.
+pub fn write_artifact(
+    path: impl AsRef<Path>,
+    envelope: &Envelope,
+    payload: &[u8],
+) -> Result<(), ArtifactError> {
+    let mut bytes = serde_json::to_vec(envelope)?;
+    bytes.push(b'\n');
+    bytes.extend_from_slice(payload);
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Reads an artifact of the given kind, rejecting other versions, fields and backends.
+pub fn read_artifact(
+    path: impl AsRef<Path>,
+    kind: Kind,
+) -> Result<(Envelope, Vec<u8>), ArtifactError> {
+    let mut bytes = fs::read(path)?;
+    let newline = bytes
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| ArtifactError::Format("no envelope".to_string()))?;
+    let envelope: Envelope = serde_json::from_slice(&bytes[..newline])?;
+
+    check("version", &FORMAT_VERSION, &envelope.version)?;
+    check("kind", &kind, &envelope.kind)?;
+    check("field", &FIELD, &envelope.field.as_str())?;
+    check("backend", &BACKEND, &envelope.backend.as_str())?;
+
+    let payload = bytes.split_off(newline + 1);
+    Ok((envelope, payload))
+}
+
+/// A hash of the verifying key: the first challenge of a transcript that only holds the
+/// key, as in every proof made with it.
+pub fn vk_fingerprint(vk: &VerifyingKey<EqAffine>) -> String {
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    vk.hash_into(&mut transcript).expect("writing to a Vec");
+    to_hex(&transcript.squeeze_challenge().get_scalar())
+}
+
+pub fn save_params(path: impl AsRef<Path>, setup: &Setup) -> Result<(), ArtifactError> {
+    let mut payload = vec![];
+    setup.params.write(&mut payload)?;
+    write_artifact(path, &Envelope::new(Kind::Params, setup.k), &payload)
+}
+
+/// Reads params saved by [`save_params`] and generates the keys of `circuit` with them.
+/// Params of another `k` are rejected.
+pub fn load_setup<C: Circuit<Fp>>(
+    path: impl AsRef<Path>,
+    k: u32,
+    circuit: &C,
+) -> Result<Setup, ArtifactError> {
+    let (envelope, payload) = read_artifact(path, Kind::Params)?;
+    check("k", &k, &envelope.k)?;
+    let params = Params::<EqAffine>::read(&mut payload.as_slice())?;
+    Ok(Setup::from_params(envelope.k, params, circuit)?)
+}
+
+/// Records the fingerprint of the verifying key of `setup`, not the key itself.
+pub fn save_vk_fingerprint(
+    path: impl AsRef<Path>,
+    circuit: &str,
+    setup: &Setup,
+) -> Result<(), ArtifactError> {
+    let envelope = Envelope::for_setup(Kind::VkFingerprint, circuit, setup);
+    let payload = vk_fingerprint(setup.vk());
+    write_artifact(path, &envelope, payload.as_bytes())
+}
+
+/// Rejects `setup` unless its verifying key is the one recorded by [`save_vk_fingerprint`].
+pub fn check_vk_fingerprint(
+    path: impl AsRef<Path>,
+    circuit: &str,
+    setup: &Setup,
+) -> Result<(), ArtifactError> {
+    let (envelope, _) = read_artifact(path, Kind::VkFingerprint)?;
+    envelope.check(&Envelope::for_setup(Kind::VkFingerprint, circuit, setup))
+}
+
+pub fn save_proof(
+    path: impl AsRef<Path>,
+    circuit: &str,
+    setup: &Setup,
+    proof: &Proof,
+) -> Result<(), ArtifactError> {
+    let envelope = Envelope::for_setup(Kind::Proof, circuit, setup);
+    write_artifact(path, &envelope, proof.as_bytes())
+}
+
+/// Reads a proof saved by [`save_proof`], rejecting the proofs of other circuits or keys.
+pub fn load_proof(
+    path: impl AsRef<Path>,
+    circuit: &str,
+    setup: &Setup,
+) -> Result<Proof, ArtifactError> {
+    let (envelope, payload) = read_artifact(path, Kind::Proof)?;
+    envelope.check(&Envelope::for_setup(Kind::Proof, circuit, setup))?;
+    Ok(Proof(payload))
+}
+
+pub fn save_instances(
+    path: impl AsRef<Path>,
+    circuit: &str,
+    k: u32,
+    instances: &[Vec<Fp>],
+) -> Result<(), ArtifactError> {
+    let columns: Vec<Vec<String>> = instances
+        .iter()
+        .map(|column| column.iter().map(to_hex).collect())
+        .collect();
+    let envelope = Envelope {
+        circuit: Some(circuit.to_string()),
+        ..Envelope::new(Kind::Instances, k)
+    };
+    write_artifact(path, &envelope, &serde_json::to_vec(&columns)?)
+}
+
+pub fn load_instances(
+    path: impl AsRef<Path>,
+    circuit: &str,
+) -> Result<Vec<Vec<Fp>>, ArtifactError> {
+    let (envelope, payload) = read_artifact(path, Kind::Instances)?;
+    check("circuit", &Some(circuit.to_string()), &envelope.circuit)?;
+
+    let columns: Vec<Vec<String>> = serde_json::from_slice(&payload)?;
+    columns
+        .iter()
+        .map(|column| column.iter().map(|value| from_hex(value)).collect())
+        .collect()
+}
+
+// The inverse of `to_hex`.
+fn from_hex(hex: &str) -> Result<Fp, ArtifactError> {
+    let invalid = || ArtifactError::Format(format!("invalid field element {}", hex));
+    let digits = hex.strip_prefix("0x").ok_or_else(invalid)?;
+    let mut repr = <Fp as PrimeField>::Repr::default();
+    if digits.len() != 2 * repr.as_ref().len() {
+        return Err(invalid());
+    }
+    for (byte, i) in repr.as_mut().iter_mut().rev().zip((0..digits.len()).step_by(2)) {
+        *byte = u8::from_str_radix(digits.get(i..i + 2).ok_or_else(invalid)?, 16)
+            .map_err(|_| invalid())?;
+    }
+    Option::from(Fp::from_repr(repr)).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{example1, example2};
+    use std::marker::PhantomData;
+
+    fn temp(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("halo2-learn-{}-{}", std::process::id(), name))
+    }
+
+    fn instances() -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]
+    }
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let circuit = example1::MyCircuit::<Fp>(PhantomData);
+        let setup = Setup::with_min_k(&circuit).unwrap();
+        let proof = setup.prove(example1::MyCircuit(PhantomData), &instances()).unwrap();
+
+        let params_path = temp("params");
+        let proof_path = temp("proof");
+        let instances_path = temp("instances");
+        save_params(&params_path, &setup).unwrap();
+        save_proof(&proof_path, "fib1", &setup, &proof).unwrap();
+        save_instances(&instances_path, "fib1", setup.k, &instances()).unwrap();
+
+        // the keys are regenerated from the cached params
+        let loaded = load_setup(&params_path, setup.k, &circuit).unwrap();
+        assert_eq!(vk_fingerprint(loaded.vk()), vk_fingerprint(setup.vk()));
+        let loaded_proof = load_proof(&proof_path, "fib1", &loaded).unwrap();
+        assert_eq!(loaded_proof, proof);
+        let loaded_instances = load_instances(&instances_path, "fib1").unwrap();
+        assert_eq!(loaded_instances, instances());
+        assert!(loaded.verify(&loaded_proof, &loaded_instances).is_accepted());
+
+        // another circuit of the same size has another key
+        let other = example2::MyCircuit::<Fp>(PhantomData);
+        let other = load_setup(&params_path, setup.k, &other).unwrap();
+        assert!(matches!(
+            load_proof(&proof_path, "fib1", &other),
+            Err(ArtifactError::Mismatch { field: "vk", .. })
+        ));
+        assert!(matches!(
+            load_proof(&proof_path, "fib2", &loaded),
+            Err(ArtifactError::Mismatch { field: "circuit", .. })
+        ));
+        assert!(matches!(
+            load_setup(&params_path, setup.k + 1, &circuit),
+            Err(ArtifactError::Mismatch { field: "k", .. })
+        ));
+        assert!(matches!(
+            read_artifact(&proof_path, Kind::Params),
+            Err(ArtifactError::Mismatch { field: "kind", .. })
+        ));
+
+        for path in [params_path, proof_path, instances_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_vk_fingerprint() {
+        let setup = Setup::with_min_k(&example1::MyCircuit::<Fp>(PhantomData)).unwrap();
+        let other = Setup::with_min_k(&example2::MyCircuit::<Fp>(PhantomData)).unwrap();
+        let path = temp("vk");
+        save_vk_fingerprint(&path, "fib1", &setup).unwrap();
+
+        check_vk_fingerprint(&path, "fib1", &setup).unwrap();
+        assert!(check_vk_fingerprint(&path, "fib1", &other).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_envelope() {
+        let json = serde_json::to_string(&Envelope::new(Kind::VkFingerprint, 4)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":2,"kind":"vk_fingerprint","circuit":null,"k":4,"field":"pasta::Fp","backend":"ipa-pasta","vk":null}"#
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        let value = -Fp::from(5);
+        assert_eq!(from_hex(&to_hex(&value)).unwrap(), value);
+        assert!(from_hex("0x12").is_err());
+        assert!(from_hex("12").is_err());
+    }
+}
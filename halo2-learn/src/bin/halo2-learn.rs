@@ -0,0 +1,709 @@
+// $ cargo run --release --bin halo2-learn -- list
+// $ cargo run --release --bin halo2-learn -- mock fib1 --public 1,1,55
+// $ cargo run --release --bin halo2-learn -- prove range-check2 --value 3 --lookup-value 200 --proof rc2.proof
+// $ cargo run --release --bin halo2-learn -- verify range-check2 --proof rc2.proof
+// $ cargo run --release --bin halo2-learn -- prove fib1 --params k4.params
+// $ cargo run --release --all-features --bin halo2-learn -- plot fib2 --output fib-2-layout.png
+// $ cargo run --release --bin halo2-learn -- table fib1
+// $ cargo run --release --bin halo2-learn -- witness fib2 --format csv --output fib2.csv
+// $ cargo run --release --bin halo2-learn -- mutate fib3 --a 10 --b 10 --c 15
+// $ cargo run --release --bin halo2-learn -- compare --n 1000 --prove
+
+use std::{error::Error, fs, marker::PhantomData, path::PathBuf, process::ExitCode};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use halo2_examples::{
+    fibonacci::{
+        compare,
+        example1,
+        example2,
+        example3::FunctionCircuit,
+        example4::{self, FibonacciCircuit},
+        example5,
+        example6,
+        example7,
+        example8,
+    },
+    artifact,
+    prover::{self, Setup},
+    range_check::{self, decompose_range_check::DecomposeRangeCheckCircuit},
+    stats::{min_k, CircuitStats},
+};
+use halo2_proofs::{
+    circuit::Value,
+    dev::{CircuitCost, MockProver},
+    pasta::{Eq, Fp},
+    plonk::Circuit,
+};
+use serde::Deserialize;
+
+#[derive(Parser)]
+#[command(name = "halo2-learn", about = "Run, prove, verify and plot the example circuits")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the example circuits
+    List,
+    /// Check the circuit with `MockProver`
+    Mock(CircuitArgs),
+    /// Generate keys and write a proof
+    Prove {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// Where to write the proof (defaults to `<example>.proof`)
+        #[arg(long)]
+        proof: Option<PathBuf>,
+        /// Params to reuse, generated and written there if the file is missing
+        #[arg(long)]
+        params: Option<PathBuf>,
+    },
+    /// Verify a proof written by `prove`
+    Verify {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// The proof to verify (defaults to `<example>.proof`)
+        #[arg(long)]
+        proof: Option<PathBuf>,
+        /// Params to reuse, generated and written there if the file is missing
+        #[arg(long)]
+        params: Option<PathBuf>,
+    },
+    /// Render the circuit layout to a PNG or an SVG (needs `--features dev-graph`)
+    Plot {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// Where to write the layout, an SVG if it ends with `.svg` (defaults to
+        /// `<example>-layout.png`)
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        layout: PlotArgs,
+    },
+    /// Print the assigned cells, regions and copy constraints as a text table
+    Table {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// Rows of each lookup table to print
+        #[arg(long, default_value_t = 4)]
+        table_rows: usize,
+        /// Don't list the copy constraints
+        #[arg(long)]
+        no_copies: bool,
+    },
+    /// Export the assigned cells with their regions and annotations
+    Witness {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        #[arg(long, value_enum, default_value_t = WitnessFormat::Json)]
+        format: WitnessFormat,
+        /// Where to write the witness (defaults to stdout)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the columns, gates, rows and minimum k of the circuit, and its proof size
+    Stats(CircuitArgs),
+    /// Change each advice cell in turn and list the changes `MockProver` still accepts
+    Mutate(CircuitArgs),
+    /// Compare the rows, columns, copies, degree and proof size of the Fibonacci layouts
+    Compare {
+        /// The term to prove, F(n)
+        #[arg(long, default_value_t = DEFAULT_N)]
+        n: usize,
+        /// Also generate the keys and time the prover
+        #[arg(long)]
+        prove: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum WitnessFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Example {
+    Fib1,
+    Fib2,
+    Fib3,
+    Fib4,
+    Fib5,
+    Fib6,
+    Fib7,
+    Fib8,
+    RangeCheck1,
+    RangeCheck2,
+    RangeCheck3,
+    Decompose,
+}
+
+impl Example {
+    fn name(self) -> &'static str {
+        match self {
+            Example::Fib1 => "fib1",
+            Example::Fib2 => "fib2",
+            Example::Fib3 => "fib3",
+            Example::Fib4 => "fib4",
+            Example::Fib5 => "fib5",
+            Example::Fib6 => "fib6",
+            Example::Fib7 => "fib7",
+            Example::Fib8 => "fib8",
+            Example::RangeCheck1 => "range-check1",
+            Example::RangeCheck2 => "range-check2",
+            Example::RangeCheck3 => "range-check3",
+            Example::Decompose => "decompose",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Example::Fib1 => "Fibonacci, three columns and one region per step (fibonacci::example1)",
+            Example::Fib2 => "Fibonacci, single column with Rotation(2) (fibonacci::example2)",
+            Example::Fib3 => "f(a, b, c) = if a == b {c} else {a - b} (fibonacci::example3)",
+            Example::Fib4 => "Fibonacci of length --n (fibonacci::example4)",
+            Example::Fib5 => "F(--n) by squaring the Fibonacci matrix (fibonacci::example5)",
+            Example::Fib6 => "F(n) by fast doubling, with n public (fibonacci::example6)",
+            Example::Fib7 => "F(--n) with 64-bit range-checked terms (fibonacci::example7)",
+            Example::Fib8 => "F(--n) mod --modulus (fibonacci::example8)",
+            Example::RangeCheck1 => "3-bit range check with a polynomial gate (range_check::example1)",
+            Example::RangeCheck2 => "3-bit gate + 8-bit lookup range check (range_check::example2)",
+            Example::RangeCheck3 => "lookup range check tagged by num_bits (range_check::example3)",
+            Example::Decompose => "running-sum range check of --num-bits bits (range_check::decompose_range_check)",
+        }
+    }
+
+    fn default_public(self) -> Vec<u64> {
+        match self {
+            // F[0], F[1], F[9]
+            Example::Fib1 | Example::Fib2 => vec![1, 1, 55],
+            // F[0], F[1]; n and F[n] are computed
+            Example::Fib4 => vec![1, 1],
+            // F[0], F[1]; F[n] is computed, and n too for fib6
+            Example::Fib5 | Example::Fib6 | Example::Fib7 | Example::Fib8 => vec![1, 1],
+            _ => vec![],
+        }
+    }
+}
+
+/// Default length of `fib4` to `fib8`, the same F[9] as fib1 and fib2.
+const DEFAULT_N: usize = 9;
+/// Default modulus of `fib8`, the first prime above 10^9.
+const DEFAULT_MODULUS: u64 = 1_000_000_007;
+
+/// Inputs that can be given either as flags or in the `--input` JSON file.
+/// Flags take precedence over the file.
+#[derive(Args, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Inputs {
+    /// log2 of the number of rows
+    #[arg(long)]
+    k: Option<u32>,
+    /// Public inputs, e.g. `--public 1,1,55`
+    #[arg(long, value_delimiter = ',')]
+    public: Option<Vec<u64>>,
+    /// Witness of the range checks (the simple value for range-check2)
+    #[arg(long)]
+    value: Option<u64>,
+    /// Lookup witness of range-check2
+    #[arg(long)]
+    lookup_value: Option<u64>,
+    /// Bit length of the value for range-check3 and decompose
+    #[arg(long)]
+    num_bits: Option<u8>,
+    /// `a` witness of fib3
+    #[arg(long)]
+    a: Option<u64>,
+    /// `b` witness of fib3
+    #[arg(long)]
+    b: Option<u64>,
+    /// `c` witness of fib3
+    #[arg(long)]
+    c: Option<u64>,
+    /// Length of fib4 to fib8
+    #[arg(long)]
+    n: Option<usize>,
+    /// Modulus of fib8
+    #[arg(long)]
+    modulus: Option<u64>,
+}
+
+#[derive(Args)]
+struct PlotArgs {
+    /// Outline the regions whose name contains this (can be repeated)
+    #[arg(long)]
+    highlight: Vec<String>,
+    /// Draw all 2^k rows and shade the unused and blinding ones
+    #[arg(long)]
+    mark_unused: bool,
+    /// Hide the region names
+    #[arg(long)]
+    no_labels: bool,
+}
+
+#[derive(Args)]
+struct CircuitArgs {
+    #[arg(value_enum)]
+    example: Example,
+    /// JSON file with any of the inputs below, e.g. `{"k": 4, "public": [1, 1, 55]}`
+    #[arg(long)]
+    input: Option<PathBuf>,
+    #[command(flatten)]
+    inputs: Inputs,
+}
+
+impl CircuitArgs {
+    /// Merges the flags over the `--input` file.
+    fn inputs(&self) -> Result<Inputs, Box<dyn Error>> {
+        let file = match &self.input {
+            Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+            None => Inputs::default(),
+        };
+        let flags = &self.inputs;
+
+        let inputs = Inputs {
+            k: flags.k.or(file.k),
+            public: flags.public.clone().or(file.public),
+            value: flags.value.or(file.value),
+            lookup_value: flags.lookup_value.or(file.lookup_value),
+            num_bits: flags.num_bits.or(file.num_bits),
+            a: flags.a.or(file.a),
+            b: flags.b.or(file.b),
+            c: flags.c.or(file.c),
+            n: flags.n.or(file.n),
+            modulus: flags.modulus.or(file.modulus),
+        };
+        self.check(&inputs)?;
+        Ok(inputs)
+    }
+
+    /// Rejects the inputs the example circuits can't be built with.
+    fn check(&self, inputs: &Inputs) -> Result<(), Box<dyn Error>> {
+        let n = inputs.n.unwrap_or(DEFAULT_N);
+        match self.example {
+            Example::Fib4 if n < 1 => Err("fib4 needs --n 1 or more".into()),
+            Example::Fib5 if n < 1 => Err("fib5 needs --n 1 or more".into()),
+            Example::Fib7 if n < 2 => Err("fib7 needs --n 2 or more".into()),
+            Example::Fib8 if n < 1 => Err("fib8 needs --n 1 or more".into()),
+            Example::Fib8 if inputs.modulus.unwrap_or(DEFAULT_MODULUS) < 2 => {
+                Err("fib8 needs --modulus 2 or more".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn fibonacci(&self, inputs: &Inputs) -> FibonacciCircuit<Fp> {
+        FibonacciCircuit::new(inputs.n.unwrap_or(DEFAULT_N))
+    }
+
+    fn instances(&self, inputs: &Inputs) -> Vec<Vec<Fp>> {
+        let public = inputs
+            .public
+            .clone()
+            .unwrap_or_else(|| self.example.default_public());
+        match self.example {
+            Example::Fib1 | Example::Fib2 => vec![public.into_iter().map(Fp::from).collect()],
+            Example::Fib4 => {
+                let public: Vec<Fp> = public.into_iter().map(Fp::from).collect();
+                match public[..] {
+                    // only the starting values: compute n and F[n] natively
+                    [f0, f1] => self.fibonacci(inputs).instances(f0, f1),
+                    _ => vec![public],
+                }
+            }
+            Example::Fib5 => {
+                let public: Vec<Fp> = public.into_iter().map(Fp::from).collect();
+                match public[..] {
+                    [f0, f1] => {
+                        let n = inputs.n.unwrap_or(DEFAULT_N);
+                        example5::FibonacciCircuit::new(n).instances(f0, f1)
+                    }
+                    _ => vec![public],
+                }
+            }
+            Example::Fib6 => {
+                let public: Vec<Fp> = public.into_iter().map(Fp::from).collect();
+                match public[..] {
+                    [f0, f1] => example6::instances(inputs.n.unwrap_or(DEFAULT_N) as u64, f0, f1),
+                    _ => vec![public],
+                }
+            }
+            Example::Fib7 => match public[..] {
+                [f0, f1] => {
+                    let n = inputs.n.unwrap_or(DEFAULT_N);
+                    // past 64 bits there is no valid F[n]: give the field one to the prover,
+                    // which rejects it
+                    example7::FibonacciCircuit::new(n)
+                        .instances(f0, f1)
+                        .unwrap_or_else(|| {
+                            let (f0, f1) = (Fp::from(f0), Fp::from(f1));
+                            vec![vec![f0, f1, example4::fibonacci(n, f0, f1)]]
+                        })
+                }
+                _ => vec![public.into_iter().map(Fp::from).collect()],
+            },
+            Example::Fib8 => match public[..] {
+                [f0, f1] => {
+                    let modulus = inputs.modulus.unwrap_or(DEFAULT_MODULUS);
+                    let n = inputs.n.unwrap_or(DEFAULT_N);
+                    // not `FibonacciCircuit::instances`, so that F[0], F[1] >= m reach the prover
+                    let out = example8::fibonacci_mod(n, f0, f1, modulus);
+                    vec![vec![Fp::from(f0), Fp::from(f1), Fp::from(out)]]
+                }
+                _ => vec![public.into_iter().map(Fp::from).collect()],
+            },
+            _ => vec![],
+        }
+    }
+}
+
+/// Builds the circuit selected by `$args` and evaluates `$body` with it bound to `$circuit`.
+macro_rules! with_circuit {
+    ($args:expr, $inputs:expr, |$circuit:ident| $body:expr) => {{
+        let inputs: &Inputs = $inputs;
+        let known = |v: Option<u64>| v.map(|v| Value::known(Fp::from(v))).unwrap_or_default();
+        match $args.example {
+            Example::Fib1 => {
+                let $circuit = example1::MyCircuit::<Fp>(PhantomData);
+                $body
+            }
+            Example::Fib2 => {
+                let $circuit = example2::MyCircuit::<Fp>(PhantomData);
+                $body
+            }
+            Example::Fib3 => {
+                let $circuit = FunctionCircuit {
+                    a: Fp::from(inputs.a.unwrap_or_default()),
+                    b: Fp::from(inputs.b.unwrap_or_default()),
+                    c: Fp::from(inputs.c.unwrap_or_default()),
+                };
+                $body
+            }
+            Example::Fib4 => {
+                let $circuit = FibonacciCircuit::<Fp>::new(inputs.n.unwrap_or(DEFAULT_N));
+                $body
+            }
+            Example::Fib5 => {
+                let n = inputs.n.unwrap_or(DEFAULT_N);
+                let $circuit = example5::FibonacciCircuit::<Fp>::new(n);
+                $body
+            }
+            Example::Fib6 => {
+                let n = inputs.n.unwrap_or(DEFAULT_N) as u64;
+                let $circuit = example6::FibonacciCircuit::<Fp>::new(n);
+                $body
+            }
+            Example::Fib7 => {
+                let n = inputs.n.unwrap_or(DEFAULT_N);
+                let $circuit = example7::FibonacciCircuit::<Fp>::new(n);
+                $body
+            }
+            Example::Fib8 => {
+                let n = inputs.n.unwrap_or(DEFAULT_N);
+                let modulus = inputs.modulus.unwrap_or(DEFAULT_MODULUS);
+                let $circuit = example8::FibonacciCircuit::<Fp>::new(n, modulus);
+                $body
+            }
+            Example::RangeCheck1 => {
+                let $circuit = range_check::example1::MyCircuit::<Fp, 8> {
+                    value: known(inputs.value).map(Into::into),
+                };
+                $body
+            }
+            Example::RangeCheck2 => {
+                let $circuit = range_check::example2::MyCircuit::<Fp, 8, 256> {
+                    simple_value: known(inputs.value).map(Into::into),
+                    lookup_value: known(inputs.lookup_value).map(Into::into),
+                };
+                $body
+            }
+            Example::RangeCheck3 => {
+                let $circuit = range_check::example3::MyCircuit::<Fp, 8, 256> {
+                    num_bits: inputs.num_bits.map(Value::known).unwrap_or_default(),
+                    value: known(inputs.value).map(Into::into),
+                };
+                $body
+            }
+            Example::Decompose => {
+                let $circuit = DecomposeRangeCheckCircuit::<Fp>::new(
+                    inputs.value.unwrap_or_default().into(),
+                    inputs.num_bits.map(usize::from).unwrap_or(8),
+                );
+                $body
+            }
+        }
+    }};
+}
+
+impl CircuitArgs {
+    /// `--k`, or the smallest `k` that fits the circuit built from these inputs.
+    fn k(&self, inputs: &Inputs) -> Result<u32, Box<dyn Error>> {
+        match inputs.k {
+            Some(k) => Ok(k),
+            None => Ok(with_circuit!(self, inputs, |circuit| min_k(&circuit)?)),
+        }
+    }
+}
+
+fn list() {
+    for example in Example::value_variants() {
+        // k for the default inputs
+        let args = CircuitArgs {
+            example: *example,
+            input: None,
+            inputs: Inputs::default(),
+        };
+        println!(
+            "{:<14} k = {:<3} {}",
+            example.name(),
+            args.k(&args.inputs).map_or("?".into(), |k| k.to_string()),
+            example.description()
+        );
+    }
+}
+
+fn mock(args: &CircuitArgs) -> Result<bool, Box<dyn Error>> {
+    let inputs = args.inputs()?;
+    let k = args.k(&inputs)?;
+    let instances = args.instances(&inputs);
+
+    let prover = with_circuit!(args, &inputs, |circuit| MockProver::run(k, &circuit, instances)?);
+    match prover.verify() {
+        Ok(()) => {
+            println!("{}: satisfied", args.example.name());
+            Ok(true)
+        }
+        Err(failures) => {
+            for failure in failures {
+                println!("{}", failure);
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Generates the params, or reads them from `params` if the file exists, and the keys.
+/// Params of another `k` than the requested one are rejected.
+fn setup<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    params: Option<&PathBuf>,
+) -> Result<Setup, Box<dyn Error>> {
+    match params {
+        Some(path) if path.exists() => Ok(artifact::load_setup(path, k, circuit)?),
+        Some(path) => {
+            let setup = Setup::new(k, circuit)?;
+            artifact::save_params(path, &setup)?;
+            Ok(setup)
+        }
+        None => Ok(Setup::new(k, circuit)?),
+    }
+}
+
+fn prove(
+    args: &CircuitArgs,
+    path: Option<PathBuf>,
+    params: Option<PathBuf>,
+) -> Result<bool, Box<dyn Error>> {
+    let inputs = args.inputs()?;
+    let k = args.k(&inputs)?;
+    let instances = args.instances(&inputs);
+    let path = path.unwrap_or_else(|| format!("{}.proof", args.example.name()).into());
+
+    let size = with_circuit!(args, &inputs, |circuit| {
+        let setup = setup(k, &circuit, params.as_ref())?;
+        let proof = setup.prove(circuit, &instances)?;
+        artifact::save_proof(&path, args.example.name(), &setup, &proof)?;
+        proof.as_bytes().len()
+    });
+    println!("wrote a {}-byte proof to {}", size, path.display());
+
+    Ok(true)
+}
+
+fn verify(
+    args: &CircuitArgs,
+    path: Option<PathBuf>,
+    params: Option<PathBuf>,
+) -> Result<bool, Box<dyn Error>> {
+    let inputs = args.inputs()?;
+    let k = args.k(&inputs)?;
+    let instances = args.instances(&inputs);
+    let path = path.unwrap_or_else(|| format!("{}.proof", args.example.name()).into());
+
+    // The keys only depend on the circuit shape, so they are regenerated here, and a proof
+    // made with other keys is rejected when it is loaded.
+    let verification = with_circuit!(args, &inputs, |circuit| {
+        let setup = setup(k, &circuit.without_witnesses(), params.as_ref())?;
+        let proof = artifact::load_proof(&path, args.example.name(), &setup)?;
+        setup.verify(&proof, &instances)
+    });
+    match verification {
+        prover::Verification::Accepted => {
+            println!("{}: proof accepted", path.display());
+            Ok(true)
+        }
+        prover::Verification::Rejected(e) => {
+            println!("{}: proof rejected ({:?})", path.display(), e);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(feature = "dev-graph")]
+fn plot(args: &CircuitArgs, path: Option<PathBuf>, layout: &PlotArgs) -> Result<bool, Box<dyn Error>> {
+    use halo2_examples::layout::{render_to_file, LayoutOptions};
+
+    let inputs = args.inputs()?;
+    let path = path.unwrap_or_else(|| format!("{}-layout.png", args.example.name()).into());
+    let options = LayoutOptions {
+        title: Some(args.example.name().into()),
+        // only an explicit `--k`; otherwise the smallest k that fits
+        k: inputs.k,
+        show_labels: !layout.no_labels,
+        highlight_regions: layout.highlight.clone(),
+        mark_unused_rows: layout.mark_unused,
+        ..Default::default()
+    };
+
+    let info = with_circuit!(args, &inputs, |circuit| render_to_file(&circuit, &path, &options)?);
+    println!("wrote {} (k = {}, {} rows used)", path.display(), info.k, info.rows);
+
+    Ok(true)
+}
+
+#[cfg(not(feature = "dev-graph"))]
+fn plot(_: &CircuitArgs, _: Option<PathBuf>, _: &PlotArgs) -> Result<bool, Box<dyn Error>> {
+    Err("plotting needs the `dev-graph` feature: cargo run --features dev-graph".into())
+}
+
+fn table(args: &CircuitArgs, table_rows: usize, no_copies: bool) -> Result<bool, Box<dyn Error>> {
+    use halo2_examples::text_layout::{render_text, TextLayoutOptions};
+
+    let inputs = args.inputs()?;
+    let instances = args.instances(&inputs);
+    let options = TextLayoutOptions {
+        table_rows,
+        show_copies: !no_copies,
+        ..Default::default()
+    };
+
+    let text = with_circuit!(args, &inputs, |circuit| render_text(&circuit, &instances, &options)?);
+    print!("{}", text);
+
+    Ok(true)
+}
+
+fn witness(
+    args: &CircuitArgs,
+    format: WitnessFormat,
+    path: Option<PathBuf>,
+) -> Result<bool, Box<dyn Error>> {
+    use halo2_examples::witness::WitnessTable;
+
+    let inputs = args.inputs()?;
+    let instances = args.instances(&inputs);
+
+    let table = with_circuit!(args, &inputs, |circuit| WitnessTable::new(&circuit, &instances)?);
+    let out = match format {
+        WitnessFormat::Json => table.to_json() + "\n",
+        WitnessFormat::Csv => table.to_csv(),
+    };
+    match path {
+        Some(path) => {
+            fs::write(&path, out)?;
+            println!("wrote {} cells to {}", table.records().len(), path.display());
+        }
+        None => print!("{}", out),
+    }
+
+    Ok(true)
+}
+
+fn stats(args: &CircuitArgs) -> Result<bool, Box<dyn Error>> {
+    let inputs = args.inputs()?;
+    let k = args.k(&inputs)?;
+
+    with_circuit!(args, &inputs, |circuit| {
+        println!("{}", CircuitStats::new(&circuit)?);
+        let cost = CircuitCost::<Eq, _>::measure(k as usize, &circuit);
+        // one circuit instance per proof
+        println!("proof size at k = {}: {} bytes", k, usize::from(cost.proof_size(1)));
+    });
+
+    Ok(true)
+}
+
+fn mutate(args: &CircuitArgs) -> Result<bool, Box<dyn Error>> {
+    use halo2_examples::mutation::find_unconstrained;
+
+    let inputs = args.inputs()?;
+    let instances = args.instances(&inputs);
+
+    let survivors = with_circuit!(args, &inputs, |circuit| {
+        find_unconstrained(&circuit, &instances)?
+    });
+    for survivor in survivors.iter() {
+        println!("{}", survivor);
+    }
+    if survivors.is_empty() {
+        println!("{}: every mutation was rejected", args.example.name());
+    }
+
+    Ok(survivors.is_empty())
+}
+
+fn compare_layouts(n: usize, prove: bool) -> Result<bool, Box<dyn Error>> {
+    println!("{}", compare::compare(n, prove)?);
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::List => {
+            list();
+            Ok(true)
+        }
+        Command::Mock(args) => mock(&args),
+        Command::Prove {
+            circuit,
+            proof,
+            params,
+        } => prove(&circuit, proof, params),
+        Command::Verify {
+            circuit,
+            proof,
+            params,
+        } => verify(&circuit, proof, params),
+        Command::Plot {
+            circuit,
+            output,
+            layout,
+        } => plot(&circuit, output, &layout),
+        Command::Table {
+            circuit,
+            table_rows,
+            no_copies,
+        } => table(&circuit, table_rows, no_copies),
+        Command::Witness {
+            circuit,
+            format,
+            output,
+        } => witness(&circuit, format, output),
+        Command::Stats(args) => stats(&args),
+        Command::Mutate(args) => mutate(&args),
+        Command::Compare { n, prove } => compare_layouts(n, prove),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use ff::{Field, PrimeField};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// Boolean logic on assigned cells. Every operation is one row: its inputs are copied
+// into a, b, c, its output is assigned in `out`, and can be copied anywhere else.
+//
+//   a | b | c | out | inv | q_bool | q_and | ... | q_select
+//  ---+---+---+-----+-----+--------+-------+-----+----------
+//   x |   |   |     |     |   1    |   0   |     |    0       assert_bool(x)
+//   x | y |   | x&y |     |   0    |   1   |     |    0       and(x, y)
+//   s | x | y | mux |     |   0    |   0   |     |    1       select(s, x, y)
+//
+// The inputs of the logic gates are constrained to be boolean again, so that the output
+// is boolean whatever the inputs were.
+//
+// `is_zero` and `is_equal` turn the `is_zero_expr` of `IsZeroChip` into an output cell:
+// `f(a, b, c) = if a == b {c} else {a - b}` of `FunctionChip` is then
+// `select(is_equal(a, b), c, a - b)`.
+
+type BinaryOp<F> = fn(Expression<F>, Expression<F>) -> Expression<F>;
+
+#[derive(Debug, Clone)]
+pub struct BooleanConfig<F: PrimeField> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub out: Column<Advice>,
+    pub q_bool: Selector,
+    pub q_and: Selector,
+    pub q_or: Selector,
+    pub q_xor: Selector,
+    pub q_not: Selector,
+    pub q_is_zero: Selector,
+    pub q_is_equal: Selector,
+    pub q_select: Selector,
+    pub q_assert_equal_if: Selector,
+    /// `a == 0`
+    pub a_is_zero: IsZeroConfig<F>,
+    /// `a - b == 0`, sharing the `inv` column with `a_is_zero`.
+    pub a_equals_b: IsZeroConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BooleanChip<F: PrimeField> {
+    config: BooleanConfig<F>,
+}
+
+impl<F: PrimeField> BooleanChip<F> {
+    pub fn construct(config: BooleanConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BooleanConfig<F> {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let out = meta.advice_column();
+        let inv = meta.advice_column();
+        for column in [a, b, c, out] {
+            meta.enable_equality(column);
+        }
+
+        let q_bool = meta.selector();
+        let q_and = meta.selector();
+        let q_or = meta.selector();
+        let q_xor = meta.selector();
+        let q_not = meta.selector();
+        let q_is_zero = meta.selector();
+        let q_is_equal = meta.selector();
+        let q_select = meta.selector();
+        let q_assert_equal_if = meta.selector();
+
+        let a_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_is_zero),
+            |meta| meta.query_advice(a, Rotation::cur()),
+            inv,
+        );
+        let a_equals_b = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_is_equal),
+            |meta| meta.query_advice(a, Rotation::cur()) - meta.query_advice(b, Rotation::cur()),
+            inv,
+        );
+
+        let one = || Expression::Constant(F::ONE);
+        let bool_check = |x: Expression<F>| x.clone() * (one() - x);
+
+        meta.create_gate("assert_bool", |meta| {
+            let q = meta.query_selector(q_bool);
+            let a = meta.query_advice(a, Rotation::cur());
+            Constraints::with_selector(q, [("a is boolean", bool_check(a))])
+        });
+
+        // The binary gates only differ in how `out` is computed from a and b.
+        let binary_gates: [(&'static str, Selector, BinaryOp<F>); 3] = [
+            ("and", q_and, |a, b| a * b),
+            ("or", q_or, |a, b| a.clone() + b.clone() - a * b),
+            ("xor", q_xor, |a, b| {
+                a.clone() + b.clone() - a * b * Expression::Constant(F::from(2))
+            }),
+        ];
+        for (name, selector, output) in binary_gates {
+            meta.create_gate(name, |meta| {
+                let q = meta.query_selector(selector);
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let out = meta.query_advice(out, Rotation::cur());
+                Constraints::with_selector(
+                    q,
+                    [
+                        ("a is boolean", bool_check(a.clone())),
+                        ("b is boolean", bool_check(b.clone())),
+                        (name, out - output(a, b)),
+                    ],
+                )
+            });
+        }
+
+        meta.create_gate("not", |meta| {
+            let q = meta.query_selector(q_not);
+            let a = meta.query_advice(a, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    ("a is boolean", bool_check(a.clone())),
+                    ("not", out - (one() - a)),
+                ],
+            )
+        });
+
+        meta.create_gate("is_zero output", |meta| {
+            let q = meta.query_selector(q_is_zero);
+            let out = meta.query_advice(out, Rotation::cur());
+            Constraints::with_selector(q, [("out = (a == 0)", out - a_is_zero.expr())])
+        });
+
+        meta.create_gate("is_equal output", |meta| {
+            let q = meta.query_selector(q_is_equal);
+            let out = meta.query_advice(out, Rotation::cur());
+            Constraints::with_selector(q, [("out = (a == b)", out - a_equals_b.expr())])
+        });
+
+        meta.create_gate("select", |meta| {
+            let q = meta.query_selector(q_select);
+            let cond = meta.query_advice(a, Rotation::cur());
+            let when_true = meta.query_advice(b, Rotation::cur());
+            let when_false = meta.query_advice(c, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    ("cond is boolean", bool_check(cond.clone())),
+                    (
+                        "out = cond ? b : c",
+                        out - (cond * (when_true - when_false.clone()) + when_false),
+                    ),
+                ],
+            )
+        });
+
+        meta.create_gate("assert_equal_if", |meta| {
+            let q = meta.query_selector(q_assert_equal_if);
+            let cond = meta.query_advice(a, Rotation::cur());
+            let lhs = meta.query_advice(b, Rotation::cur());
+            let rhs = meta.query_advice(c, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    ("cond is boolean", bool_check(cond.clone())),
+                    ("cond => b == c", cond * (lhs - rhs)),
+                ],
+            )
+        });
+
+        BooleanConfig {
+            a,
+            b,
+            c,
+            out,
+            q_bool,
+            q_and,
+            q_or,
+            q_xor,
+            q_not,
+            q_is_zero,
+            q_is_equal,
+            q_select,
+            q_assert_equal_if,
+            a_is_zero,
+            a_equals_b,
+        }
+    }
+
+    /// Witnesses a value, without any constraint.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "assign",
+            |mut region| region.assign_advice(|| "value", self.config.out, 0, || value),
+        )
+    }
+
+    /// Witnesses a boolean.
+    pub fn assign_bool(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "assign_bool",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                region.assign_advice(|| "value", self.config.a, 0, || value.map(bool_to_field))
+            },
+        )
+    }
+
+    /// Constrains `x` to be 0 or 1, and returns the copy that was checked.
+    pub fn assert_bool(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "assert_bool",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, self.config.a, 0)
+            },
+        )
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate(layouter, "and", self.config.q_and, &[x, y], |v| v[0] * v[1])
+    }
+
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate(layouter, "or", self.config.q_or, &[x, y], |v| {
+            v[0] + v[1] - v[0] * v[1]
+        })
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate(layouter, "xor", self.config.q_xor, &[x, y], |v| {
+            v[0] + v[1] - v[0] * v[1] * Value::known(F::from(2))
+        })
+    }
+
+    pub fn not(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate(layouter, "not", self.config.q_not, &[x], |v| {
+            Value::known(F::ONE) - v[0]
+        })
+    }
+
+    /// `x == 0`, as a boolean cell.
+    pub fn is_zero(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.a_is_zero.clone());
+        self.gate_with(layouter, "is_zero", self.config.q_is_zero, &[x], |region, v| {
+            is_zero_chip.assign(region, 0, v[0])?;
+            Ok(v[0].map(|x| bool_to_field(x.is_zero_vartime())))
+        })
+    }
+
+    /// `x != 0`, as a boolean cell.
+    pub fn is_non_zero(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let is_zero = self.is_zero(layouter.namespace(|| "is_zero"), x)?;
+        self.not(layouter.namespace(|| "not"), &is_zero)
+    }
+
+    /// `x == y`, as a boolean cell.
+    pub fn is_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.a_equals_b.clone());
+        self.gate_with(layouter, "is_equal", self.config.q_is_equal, &[x, y], |region, v| {
+            is_zero_chip.assign(region, 0, v[0] - v[1])?;
+            Ok(v[0].zip(v[1]).map(|(x, y)| bool_to_field(x == y)))
+        })
+    }
+
+    /// `if cond { when_true } else { when_false }`; `cond` must be boolean.
+    pub fn select(
+        &self,
+        layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        when_true: &AssignedCell<F, F>,
+        when_false: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate(
+            layouter,
+            "select",
+            self.config.q_select,
+            &[cond, when_true, when_false],
+            |v| v[0] * (v[1] - v[2]) + v[2],
+        )
+    }
+
+    /// Constrains `x == y` when `cond` is 1, and nothing when it is 0.
+    pub fn assert_equal_if(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert_equal_if",
+            |mut region| {
+                self.config.q_assert_equal_if.enable(&mut region, 0)?;
+                cond.copy_advice(|| "cond", &mut region, self.config.a, 0)?;
+                x.copy_advice(|| "x", &mut region, self.config.b, 0)?;
+                y.copy_advice(|| "y", &mut region, self.config.c, 0)?;
+                Ok(())
+            },
+        )
+    }
+
+    // One row: copies the inputs into a, b, c and assigns `output(inputs)` in `out`.
+    fn gate(
+        &self,
+        layouter: impl Layouter<F>,
+        name: &str,
+        selector: Selector,
+        inputs: &[&AssignedCell<F, F>],
+        output: impl Fn(&[Value<F>]) -> Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.gate_with(layouter, name, selector, inputs, |_, v| Ok(output(v)))
+    }
+
+    // Same as `gate`, when the output also needs other cells of the row to be assigned.
+    fn gate_with(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        selector: Selector,
+        inputs: &[&AssignedCell<F, F>],
+        output: impl Fn(&mut Region<'_, F>, &[Value<F>]) -> Result<Value<F>, Error>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let columns = [self.config.a, self.config.b, self.config.c];
+        assert!(inputs.len() <= columns.len());
+
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                let values = inputs
+                    .iter()
+                    .zip(columns)
+                    .enumerate()
+                    .map(|(i, (input, column))| {
+                        input
+                            .copy_advice(|| format!("input {}", i), &mut region, column, 0)
+                            .map(|cell| cell.value().copied())
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let out = output(&mut region, &values)?;
+                region.assign_advice(|| name, self.config.out, 0, || out)
+            },
+        )
+    }
+}
+
+/// 1 for `true`, 0 for `false`.
+pub(crate) fn bool_to_field<F: Field>(b: bool) -> F {
+    if b {
+        F::ONE
+    } else {
+        F::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::stats::min_k;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Op {
+        AssertBool,
+        And,
+        Or,
+        Xor,
+        Not,
+        IsZero,
+        IsNonZero,
+        IsEqual,
+        Select,
+        AssertEqualIf,
+    }
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        boolean: BooleanConfig<Fp>,
+        instance: Column<Instance>,
+    }
+
+    /// Applies a single operation to witnessed inputs, and exposes its output (nothing for
+    /// `AssertEqualIf`).
+    #[derive(Clone)]
+    struct OpCircuit {
+        op: Op,
+        inputs: Vec<Value<Fp>>,
+    }
+
+    impl Circuit<Fp> for OpCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                op: self.op,
+                inputs: vec![Value::unknown(); self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let boolean = BooleanChip::configure(meta);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig { boolean, instance }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BooleanChip::construct(config.boolean);
+            let inputs = self
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    chip.assign(layouter.namespace(|| format!("input {}", i)), *value)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let ns = layouter.namespace(|| format!("{:?}", self.op));
+            let out = match (self.op, &inputs[..]) {
+                (Op::AssertBool, [x]) => chip.assert_bool(ns, x)?,
+                (Op::And, [x, y]) => chip.and(ns, x, y)?,
+                (Op::Or, [x, y]) => chip.or(ns, x, y)?,
+                (Op::Xor, [x, y]) => chip.xor(ns, x, y)?,
+                (Op::Not, [x]) => chip.not(ns, x)?,
+                (Op::IsZero, [x]) => chip.is_zero(ns, x)?,
+                (Op::IsNonZero, [x]) => chip.is_non_zero(ns, x)?,
+                (Op::IsEqual, [x, y]) => chip.is_equal(ns, x, y)?,
+                (Op::Select, [cond, x, y]) => chip.select(ns, cond, x, y)?,
+                (Op::AssertEqualIf, [cond, x, y]) => {
+                    return chip.assert_equal_if(ns, cond, x, y)
+                }
+                _ => panic!("wrong number of inputs for {:?}", self.op),
+            };
+            layouter.constrain_instance(out.cell(), config.instance, 0)
+        }
+    }
+
+    fn run(op: Op, inputs: &[Fp], public: Vec<Fp>) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = OpCircuit {
+            op,
+            inputs: inputs.iter().copied().map(Value::known).collect(),
+        };
+        MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![public]).unwrap().verify()
+    }
+
+    fn fp(values: &[u64]) -> Vec<Fp> {
+        values.iter().copied().map(Fp::from).collect()
+    }
+
+    /// The output of the logic gates as polynomials, also defined for non-boolean inputs.
+    fn polynomial(op: Op, v: &[Fp]) -> Fp {
+        match op {
+            Op::AssertBool => v[0],
+            Op::And => v[0] * v[1],
+            Op::Or => v[0] + v[1] - v[0] * v[1],
+            Op::Xor => v[0] + v[1] - v[0] * v[1] * Fp::from(2),
+            Op::Not => Fp::one() - v[0],
+            Op::Select => v[0] * (v[1] - v[2]) + v[2],
+            _ => unreachable!("{:?} is not a polynomial of its inputs", op),
+        }
+    }
+
+    #[test]
+    fn test_truth_tables() {
+        for x in [false, true] {
+            let not = run(Op::Not, &fp(&[x as u64]), fp(&[!x as u64]));
+            assert_eq!(not, Ok(()));
+            assert_eq!(run(Op::AssertBool, &fp(&[x as u64]), fp(&[x as u64])), Ok(()));
+
+            for y in [false, true] {
+                let inputs = fp(&[x as u64, y as u64]);
+                for (op, out) in [(Op::And, x & y), (Op::Or, x | y), (Op::Xor, x ^ y)] {
+                    assert_eq!(run(op, &inputs, fp(&[out as u64])), Ok(()), "{:?}", op);
+                    assert!(run(op, &inputs, fp(&[!out as u64])).is_err(), "{:?}", op);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_zero_and_is_equal() {
+        let values = [Fp::zero(), Fp::one(), Fp::from(5), -Fp::one()];
+        for x in values {
+            let is_zero = x == Fp::zero();
+            assert_eq!(run(Op::IsZero, &[x], fp(&[is_zero as u64])), Ok(()));
+            assert_eq!(run(Op::IsNonZero, &[x], fp(&[!is_zero as u64])), Ok(()));
+            assert!(run(Op::IsZero, &[x], fp(&[!is_zero as u64])).is_err());
+
+            for y in values {
+                assert_eq!(run(Op::IsEqual, &[x, y], fp(&[(x == y) as u64])), Ok(()));
+                assert!(run(Op::IsEqual, &[x, y], fp(&[(x != y) as u64])).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        for cond in [0, 1] {
+            let out = if cond == 1 { 7 } else { 9 };
+            assert_eq!(run(Op::Select, &fp(&[cond, 7, 9]), fp(&[out])), Ok(()));
+            assert!(run(Op::Select, &fp(&[cond, 7, 9]), fp(&[16 - out])).is_err());
+        }
+    }
+
+    #[test]
+    fn test_assert_equal_if() {
+        assert_eq!(run(Op::AssertEqualIf, &fp(&[0, 7, 2]), vec![]), Ok(()));
+        assert_eq!(run(Op::AssertEqualIf, &fp(&[1, 7, 7]), vec![]), Ok(()));
+        assert!(run(Op::AssertEqualIf, &fp(&[1, 7, 2]), vec![]).is_err());
+        // with cond = 2, cond * (b - c) = 0 would still need b == c
+        assert!(run(Op::AssertEqualIf, &fp(&[2, 7, 7]), vec![]).is_err());
+    }
+
+    #[test]
+    fn test_not_boolean() {
+        // The public output is what the gate computes from the same inputs, so that only
+        // the boolean constraints can fail.
+        let cases = [
+            (Op::AssertBool, fp(&[2])),
+            (Op::And, fp(&[2, 1])),
+            (Op::And, fp(&[1, 2])),
+            (Op::Or, fp(&[2, 0])),
+            (Op::Xor, fp(&[0, 2])),
+            (Op::Not, fp(&[2])),
+            (Op::Select, fp(&[2, 7, 9])),
+        ];
+        for (op, inputs) in cases {
+            let public = vec![polynomial(op, &inputs)];
+            let failures = run(op, &inputs, public).unwrap_err();
+            assert!(
+                failures
+                    .iter()
+                    .all(|failure| matches!(failure, VerifyFailure::ConstraintNotSatisfied { .. })),
+                "{:?}: {:?}",
+                op,
+                failures
+            );
+        }
+    }
+}
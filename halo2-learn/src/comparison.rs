@@ -0,0 +1,448 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use crate::boolean::bool_to_field;
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+use crate::range_check::chip::{RangeCheckChip, RangeCheckConfig, RangeConstrained};
+
+// Comparisons of N-bit values. For a, b < 2^N:
+//
+//     diff = a - b + lt * 2^N,   lt boolean,   diff < 2^N
+//
+// If a >= b, lt = 1 would make diff >= 2^N; if a < b, lt = 0 would make diff negative,
+// i.e. a field element close to p. So lt is fixed to [a < b] by the range check of diff,
+// which goes through the `RangeCheckChip` and its lookup table. The inputs must have
+// been range-checked already, which is why they are `RangeConstrained`.
+//
+// a == b is the `IsZeroChip` on a - b, and min / max select on lt.
+//
+//   a | b | lt | eq | le | diff | inv | min | max | q_compare
+//  ---+---+----+----+----+------+-----+-----+-----+-----------
+//   3 | 5 |  1 |  0 |  1 | 254  | ... |  3  |  5  |     1        N = 8
+
+/// The outputs of [`ComparisonChip::compare`]. `lt`, `eq` and `le` are boolean cells.
+#[derive(Debug, Clone)]
+pub struct Comparison<F: PrimeFieldBits> {
+    pub lt: AssignedCell<F, F>,
+    pub eq: AssignedCell<F, F>,
+    pub le: AssignedCell<F, F>,
+    pub min: RangeConstrained<F>,
+    pub max: RangeConstrained<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparisonConfig<F: PrimeFieldBits> {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub lt: Column<Advice>,
+    pub eq: Column<Advice>,
+    pub le: Column<Advice>,
+    pub diff: Column<Advice>,
+    pub min: Column<Advice>,
+    pub max: Column<Advice>,
+    pub q_compare: Selector,
+    pub a_equals_b: IsZeroConfig<F>,
+    pub range: RangeCheckConfig<F>,
+    /// N, the number of bits of the compared values.
+    pub num_bits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparisonChip<F: PrimeFieldBits> {
+    config: ComparisonConfig<F>,
+}
+
+impl<F: PrimeFieldBits> ComparisonChip<F> {
+    pub fn construct(config: ComparisonConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// `range` is the range-check chip used for `diff`; its table can be shared with
+    /// the rest of the circuit.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        range: RangeCheckConfig<F>,
+        num_bits: usize,
+    ) -> ComparisonConfig<F> {
+        // a - b + 2^N must not wrap around the modulus
+        assert!(num_bits > 0 && num_bits + 1 < F::NUM_BITS as usize);
+
+        let q_compare = meta.selector();
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let lt = meta.advice_column();
+        let eq = meta.advice_column();
+        let le = meta.advice_column();
+        let diff = meta.advice_column();
+        let min = meta.advice_column();
+        let max = meta.advice_column();
+        let inv = meta.advice_column();
+
+        for column in [a, b, lt, eq, le, diff, min, max] {
+            meta.enable_equality(column);
+        }
+
+        let a_equals_b = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| meta.query_advice(a, Rotation::cur()) - meta.query_advice(b, Rotation::cur()),
+            inv,
+        );
+
+        let two_pow_n = F::from(2).pow_vartime([num_bits as u64]);
+        meta.create_gate("compare", |meta| {
+            let q = meta.query_selector(q_compare);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let eq = meta.query_advice(eq, Rotation::cur());
+            let le = meta.query_advice(le, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let min = meta.query_advice(min, Rotation::cur());
+            let max = meta.query_advice(max, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("lt is boolean", lt.clone() * (one - lt.clone())),
+                    (
+                        "diff = a - b + lt * 2^N",
+                        a.clone() - b.clone() + lt.clone() * Expression::Constant(two_pow_n) - diff,
+                    ),
+                    ("eq = (a == b)", eq.clone() - a_equals_b.expr()),
+                    // lt and eq are never both 1
+                    ("le = lt + eq", le - lt.clone() - eq),
+                    ("min = lt ? a : b", min.clone() - (lt * (a.clone() - b.clone()) + b.clone())),
+                    ("max = a + b - min", max - (a + b - min)),
+                ],
+            )
+        });
+
+        ComparisonConfig {
+            a,
+            b,
+            lt,
+            eq,
+            le,
+            diff,
+            min,
+            max,
+            q_compare,
+            a_equals_b,
+            range,
+            num_bits,
+        }
+    }
+
+    fn range_chip(&self) -> RangeCheckChip<F> {
+        RangeCheckChip::construct(self.config.range.clone())
+    }
+
+    /// Witnesses an N-bit value, ready to be compared.
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        self.range_chip().assign(layouter, value, self.config.num_bits)
+    }
+
+    /// Compares `a` and `b` in a single row.
+    pub fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<Comparison<F>, Error> {
+        let num_bits = self.config.num_bits;
+        assert!(
+            a.num_bits <= num_bits && b.num_bits <= num_bits,
+            "inputs must have at most {} bits",
+            num_bits
+        );
+        let is_zero_chip = IsZeroChip::construct(self.config.a_equals_b.clone());
+        let two_pow_n = F::from(2).pow_vartime([num_bits as u64]);
+
+        let (lt, eq, le, diff, min, max) = layouter.assign_region(
+            || "compare",
+            |mut region| {
+                self.config.q_compare.enable(&mut region, 0)?;
+                let a = a.cell.copy_advice(|| "a", &mut region, self.config.a, 0)?;
+                let b = b.cell.copy_advice(|| "b", &mut region, self.config.b, 0)?;
+
+                let a = a.value().copied();
+                let b = b.value().copied();
+                let is_lt = a.zip(b).map(|(a, b)| less_than(&a, &b));
+                let is_eq = a.zip(b).map(|(a, b)| a == b);
+                is_zero_chip.assign(&mut region, 0, a - b)?;
+
+                let lt = region.assign_advice(|| "lt", self.config.lt, 0, || is_lt.map(bool_to_field))?;
+                let eq = region.assign_advice(|| "eq", self.config.eq, 0, || is_eq.map(bool_to_field))?;
+                let le = region.assign_advice(
+                    || "le",
+                    self.config.le,
+                    0,
+                    || lt.value().copied() + eq.value(),
+                )?;
+                let diff = region.assign_advice(
+                    || "diff",
+                    self.config.diff,
+                    0,
+                    || a - b + lt.value().map(|lt| *lt * two_pow_n),
+                )?;
+                let min = region.assign_advice(
+                    || "min",
+                    self.config.min,
+                    0,
+                    || is_lt.zip(a.zip(b)).map(|(is_lt, (a, b))| if is_lt { a } else { b }),
+                )?;
+                let max = region.assign_advice(
+                    || "max",
+                    self.config.max,
+                    0,
+                    || is_lt.zip(a.zip(b)).map(|(is_lt, (a, b))| if is_lt { b } else { a }),
+                )?;
+
+                Ok((lt, eq, le, diff, min, max))
+            },
+        )?;
+
+        self.range_chip()
+            .copy_check(layouter.namespace(|| "diff"), &diff, num_bits)?;
+
+        // min and max are one of the inputs, so they keep the widest of the two bounds.
+        let bits = a.num_bits.max(b.num_bits);
+        Ok(Comparison {
+            lt,
+            eq,
+            le,
+            min: RangeConstrained { cell: min, num_bits: bits },
+            max: RangeConstrained { cell: max, num_bits: bits },
+        })
+    }
+
+    /// `a < b`
+    pub fn less_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.lt)
+    }
+
+    /// `a <= b`
+    pub fn less_or_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.le)
+    }
+
+    /// `a > b`, i.e. `b < a`
+    pub fn greater_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, b, a)?.lt)
+    }
+
+    /// `a >= b`, i.e. `b <= a`
+    pub fn greater_or_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, b, a)?.le)
+    }
+
+    pub fn min(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        Ok(self.compare(layouter, a, b)?.min)
+    }
+
+    pub fn max(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &RangeConstrained<F>,
+        b: &RangeConstrained<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        Ok(self.compare(layouter, a, b)?.max)
+    }
+
+    /// `min(max(value, lo), hi)`. When `lo > hi` the result is `hi`.
+    pub fn clamp(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &RangeConstrained<F>,
+        lo: &RangeConstrained<F>,
+        hi: &RangeConstrained<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        let value = self.max(layouter.namespace(|| "clamp lo"), value, lo)?;
+        self.min(layouter.namespace(|| "clamp hi"), &value, hi)
+    }
+}
+
+/// `a < b` as integers in `[0, p)`.
+fn less_than<F: PrimeFieldBits>(a: &F, b: &F) -> bool {
+    let a: Vec<bool> = a.to_le_bits().iter().by_vals().collect();
+    let b: Vec<bool> = b.to_le_bits().iter().by_vals().collect();
+    a.iter()
+        .zip(b.iter())
+        .rev()
+        .find(|(a, b)| a != b)
+        .is_some_and(|(a, b)| !a & b)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::stats::min_k;
+
+    const NUM_BITS: usize = 8;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig<F: PrimeFieldBits> {
+        comparison: ComparisonConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    /// Exposes `[a < b, a <= b, a > b, a >= b, a == b, min, max, clamp(c, min, max)]`.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeFieldBits> {
+        a: Value<F>,
+        b: Value<F>,
+        c: Value<F>,
+    }
+
+    impl<F: PrimeFieldBits> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let value = meta.advice_column();
+            let range = RangeCheckChip::configure(meta, value, 0, 4);
+            let comparison = ComparisonChip::configure(meta, range, NUM_BITS);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            TestConfig {
+                comparison,
+                instance,
+            }
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let chip = ComparisonChip::construct(config.comparison);
+            RangeCheckChip::construct(chip.config.range.clone()).load_table(&mut layouter)?;
+
+            let a = chip.assign(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.assign(layouter.namespace(|| "b"), self.b)?;
+            let c = chip.assign(layouter.namespace(|| "c"), self.c)?;
+
+            let cmp = chip.compare(layouter.namespace(|| "a ? b"), &a, &b)?;
+            let gt = chip.greater_than(layouter.namespace(|| "a > b"), &a, &b)?;
+            let ge = chip.greater_or_equal(layouter.namespace(|| "a >= b"), &a, &b)?;
+            let clamped = chip.clamp(layouter.namespace(|| "clamp"), &c, &cmp.min, &cmp.max)?;
+
+            let outputs = [cmp.lt, cmp.le, gt, ge, cmp.eq, cmp.min.cell, cmp.max.cell, clamped.cell];
+            for (row, cell) in outputs.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn run(a: u64, b: u64, c: u64, public: Vec<Fp>) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            c: Value::known(Fp::from(c)),
+        };
+        MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![public]).unwrap().verify()
+    }
+
+    fn expected(a: u64, b: u64, c: u64) -> Vec<Fp> {
+        let (min, max) = (a.min(b), a.max(b));
+        [
+            (a < b) as u64,
+            (a <= b) as u64,
+            (a > b) as u64,
+            (a >= b) as u64,
+            (a == b) as u64,
+            min,
+            max,
+            c.clamp(min, max),
+        ]
+        .into_iter()
+        .map(Fp::from)
+        .collect()
+    }
+
+    #[test]
+    fn test_comparison() {
+        let max = (1 << NUM_BITS) - 1;
+        let values = [0, 1, 2, 100, max - 1, max];
+
+        for a in values {
+            for b in values {
+                for c in [0, 50, max] {
+                    assert_eq!(run(a, b, c, expected(a, b, c)), Ok(()), "a = {}, b = {}, c = {}", a, b, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparison_wrong_output() {
+        let max = (1 << NUM_BITS) - 1;
+        for (a, b) in [(0, 0), (0, max), (max, 0), (max, max), (7, 8)] {
+            for row in 0..8 {
+                let mut public = expected(a, b, 3);
+                public[row] += Fp::one();
+                assert!(run(a, b, 3, public).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparison_out_of_range() {
+        // 2^N does not fit; whatever the outputs, the range checks fail
+        let too_big = 1 << NUM_BITS;
+        assert!(run(too_big, 0, 0, expected(too_big, 0, 0)).is_err());
+        assert!(run(0, too_big, 0, expected(0, too_big, 0)).is_err());
+    }
+
+    #[test]
+    fn test_native_less_than() {
+        assert!(less_than(&Fp::from(0), &Fp::from(1)));
+        assert!(!less_than(&Fp::from(1), &Fp::from(1)));
+        assert!(!less_than(&Fp::from(256), &Fp::from(255)));
+        assert!(less_than(&Fp::from(255), &-Fp::one()));
+    }
+}
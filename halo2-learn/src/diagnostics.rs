@@ -0,0 +1,542 @@
+//! Turns the failures of `MockProver` into reports: the names of the gate, constraint and
+//! region, the row in the whole circuit, and the offending cells with the annotation they
+//! were assigned with.
+//!
+//! ```text
+//! constraint "range check" of gate "range check" is not satisfied
+//!   at row 0, offset 0 of region "Assign value"
+//!   a0[0] = 8 "value"
+//! ```
+//!
+//! Tests can match the failures by name instead of spelling out the exact `VerifyFailure`s:
+//!
+//! ```ignore
+//! let report = diagnose(&circuit, vec![])?;
+//! expect_failure()
+//!     .constraint("range check")
+//!     .in_region("Assign value")
+//!     .assert(&report);
+//! ```
+
+use std::fmt;
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::{
+    dev::{FailureLocation, MockProver, VerifyFailure},
+    plonk::{Any, Circuit, Error},
+};
+
+use crate::{stats::min_k, text_layout::format_value, witness::WitnessTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Constraint,
+    /// A constraint evaluated on an unknown value.
+    Poisoned,
+    Lookup,
+    Permutation,
+    /// A gate queries a cell that is not assigned.
+    NotAssigned,
+}
+
+/// A cell of a failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedCell {
+    /// The column as named in the text tables, e.g. `a0`.
+    pub column: String,
+    /// `None` when the failure has no location, as for poisoned constraints.
+    pub row: Option<usize>,
+    /// The rotation of the query, for the cells of a constraint.
+    pub rotation: i32,
+    /// As formatted by [`format_value`], `None` when the cell is not assigned.
+    pub value: Option<String>,
+    pub annotation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: FailureKind,
+    pub gate: Option<String>,
+    pub constraint: Option<String>,
+    pub lookup: Option<usize>,
+    pub region: Option<String>,
+    /// The offset of the failure in `region`.
+    pub offset: Option<usize>,
+    pub row: Option<usize>,
+    pub cells: Vec<FailedCell>,
+}
+
+impl Diagnostic {
+    fn new(kind: FailureKind) -> Self {
+        Self {
+            kind,
+            gate: None,
+            constraint: None,
+            lookup: None,
+            region: None,
+            offset: None,
+            row: None,
+            cells: vec![],
+        }
+    }
+
+    /// Reads `failure`, with the rows and annotations of the cells from `table`.
+    pub fn from_failure<F: PrimeField>(failure: &VerifyFailure, table: &WitnessTable<F>) -> Self {
+        match failure {
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                cell_values,
+            } => {
+                let mut diagnostic = Self::new(FailureKind::Constraint);
+                diagnostic.set_constraint(&constraint.to_string());
+                diagnostic.set_location(location, table);
+                // the values are read from `table` like for the other failures, rather than
+                // kept in the hex form of `MockProver`
+                for (cell, _) in cell_values {
+                    let Some((kind, index, rotation)) = parse_virtual_cell(&cell.to_string()) else {
+                        continue;
+                    };
+                    let row = diagnostic
+                        .row
+                        .and_then(|row| usize::try_from(row as i64 + rotation as i64).ok());
+                    let mut cell = diagnostic.cell(table, kind, index, row);
+                    cell.rotation = rotation;
+                    diagnostic.cells.push(cell);
+                }
+                diagnostic
+            }
+            VerifyFailure::ConstraintPoisoned { constraint } => {
+                let mut diagnostic = Self::new(FailureKind::Poisoned);
+                diagnostic.set_constraint(&constraint.to_string());
+                diagnostic
+            }
+            VerifyFailure::Lookup {
+                lookup_index,
+                location,
+            } => {
+                let mut diagnostic = Self::new(FailureKind::Lookup);
+                diagnostic.lookup = Some(*lookup_index);
+                diagnostic.set_location(location, table);
+                // the lookup inputs are not reported, so list the advice cells of the row
+                if let Some(row) = diagnostic.row {
+                    for column in table.columns() {
+                        let info = table.info(column, row);
+                        if *column.column_type() == Any::Advice
+                            && info.map(|info| &info.region) == Some(&diagnostic.region)
+                        {
+                            let index = column.index();
+                            let cell = diagnostic.cell(table, Any::Advice, index, Some(row));
+                            diagnostic.cells.push(cell);
+                        }
+                    }
+                }
+                diagnostic
+            }
+            VerifyFailure::Permutation { column, location } => {
+                let mut diagnostic = Self::new(FailureKind::Permutation);
+                diagnostic.set_location(location, table);
+                if let Some((kind, index)) = parse_column(&column.to_string()) {
+                    let cell = diagnostic.cell(table, kind, index, diagnostic.row);
+                    diagnostic.cells.push(cell);
+                }
+                diagnostic
+            }
+            VerifyFailure::CellNotAssigned {
+                gate,
+                region,
+                gate_offset,
+                column,
+                offset,
+            } => {
+                let mut diagnostic = Self::new(FailureKind::NotAssigned);
+                diagnostic.gate = parse_named(&gate.to_string()).map(|(_, name)| name);
+                if let Some((index, name)) = parse_named(&region.to_string()) {
+                    let start = table.region_by_index(index).map(|region| region.rows.start);
+                    diagnostic.region = Some(name);
+                    diagnostic.offset = Some(*gate_offset);
+                    diagnostic.row = start.map(|start| start + gate_offset);
+                    let row = start.and_then(|start| usize::try_from(start as isize + offset).ok());
+                    let cell = diagnostic.cell(table, *column.column_type(), column.index(), row);
+                    diagnostic.cells.push(cell);
+                }
+                diagnostic
+            }
+        }
+    }
+
+    // "Constraint 0 ('range check') in gate 0 ('range check')", the name of the constraint
+    // is left out when it is empty.
+    fn set_constraint(&mut self, constraint: &str) {
+        if let Some((constraint, gate)) = constraint.split_once(" in gate ") {
+            self.constraint = parse_named(constraint).map(|(_, name)| name);
+            self.gate = parse_named(&format!("Gate {}", gate)).map(|(_, name)| name);
+        }
+    }
+
+    fn set_location<F: PrimeField>(&mut self, location: &FailureLocation, table: &WitnessTable<F>) {
+        match location {
+            FailureLocation::InRegion { region, offset } => {
+                if let Some((index, name)) = parse_named(&region.to_string()) {
+                    self.row = table
+                        .region_by_index(index)
+                        .map(|region| region.rows.start + offset);
+                    self.region = Some(name);
+                }
+                self.offset = Some(*offset);
+            }
+            FailureLocation::OutsideRegion { row } => self.row = Some(*row),
+        }
+    }
+
+    fn cell<F: PrimeField>(
+        &self,
+        table: &WitnessTable<F>,
+        kind: Any,
+        index: usize,
+        row: Option<usize>,
+    ) -> FailedCell {
+        let column = table
+            .columns()
+            .into_iter()
+            .find(|column| *column.column_type() == kind && column.index() == index);
+        let name = match column {
+            Some(column) => table.column_name(&column),
+            None => format!("{}{}", column_prefix(kind), index),
+        };
+        let (value, annotation) = match (column, row) {
+            (Some(column), Some(row)) => (
+                table.value(column, row).flatten().map(|value| format_value(&value)),
+                table.info(column, row).and_then(|info| info.annotation.clone()),
+            ),
+            _ => (None, None),
+        };
+        FailedCell {
+            column: name,
+            row,
+            rotation: 0,
+            value,
+            annotation,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gate = self.gate.as_deref().unwrap_or_default();
+        let constraint = match &self.constraint {
+            Some(name) => format!("constraint \"{}\"", name),
+            None => "a constraint".to_string(),
+        };
+        match self.kind {
+            FailureKind::Constraint => {
+                write!(f, "{} of gate \"{}\" is not satisfied", constraint, gate)?
+            }
+            FailureKind::Poisoned => write!(f, "{} of gate \"{}\" is poisoned", constraint, gate)?,
+            FailureKind::Lookup => {
+                write!(f, "lookup {} is not satisfied", self.lookup.unwrap_or_default())?
+            }
+            FailureKind::Permutation => write!(f, "copy constraint is not satisfied")?,
+            FailureKind::NotAssigned => {
+                write!(f, "gate \"{}\" queries a cell that is not assigned", gate)?
+            }
+        }
+
+        let location = match (&self.row, &self.region, &self.offset) {
+            (Some(row), Some(region), Some(offset)) => {
+                format!("at row {}, offset {} of region \"{}\"", row, offset, region)
+            }
+            (None, Some(region), Some(offset)) => {
+                format!("at offset {} of region \"{}\"", offset, region)
+            }
+            (Some(row), _, _) => format!("at row {}", row),
+            _ => String::new(),
+        };
+        if !location.is_empty() {
+            write!(f, "\n  {}", location)?;
+        }
+
+        for cell in self.cells.iter() {
+            match cell.row {
+                Some(row) => write!(f, "\n  {}[{}]", cell.column, row)?,
+                None => write!(f, "\n  {}@{}", cell.column, cell.rotation)?,
+            }
+            write!(f, " = {}", cell.value.as_deref().unwrap_or("?"))?;
+            if let Some(annotation) = &cell.annotation {
+                write!(f, " \"{}\"", annotation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The failures of one `MockProver` run.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn new<F: PrimeField>(failures: &[VerifyFailure], table: &WitnessTable<F>) -> Self {
+        Self {
+            diagnostics: failures
+                .iter()
+                .map(|failure| Diagnostic::from_failure(failure, table))
+                .collect(),
+        }
+    }
+
+    pub fn is_satisfied(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_satisfied() {
+            return write!(f, "satisfied");
+        }
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `MockProver` at the smallest `k` that fits `circuit` and reports its failures.
+pub fn diagnose<F, C>(circuit: &C, instances: Vec<Vec<F>>) -> Result<Report, Error>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F>,
+{
+    let table = WitnessTable::new(circuit, &instances)?;
+    let prover = MockProver::run(min_k(circuit)?, circuit, instances)?;
+    let failures = prover.verify().err().unwrap_or_default();
+    Ok(Report::new(&failures, &table))
+}
+
+/// Matches the diagnostics with the given properties, see [`expect_failure`].
+#[derive(Debug, Clone, Default)]
+pub struct FailureMatcher {
+    kind: Option<FailureKind>,
+    gate: Option<String>,
+    constraint: Option<String>,
+    lookup: Option<usize>,
+    region: Option<String>,
+    offset: Option<usize>,
+    row: Option<usize>,
+}
+
+/// A matcher for any failure, to narrow down with its builder methods.
+pub fn expect_failure() -> FailureMatcher {
+    FailureMatcher::default()
+}
+
+impl FailureMatcher {
+    pub fn kind(mut self, kind: FailureKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// An unsatisfied constraint with this name.
+    pub fn constraint(mut self, name: &str) -> Self {
+        self.kind = Some(FailureKind::Constraint);
+        self.constraint = Some(name.to_string());
+        self
+    }
+
+    pub fn gate(mut self, name: &str) -> Self {
+        self.gate = Some(name.to_string());
+        self
+    }
+
+    /// An unsatisfied lookup, by the order the lookups are configured in.
+    pub fn lookup(mut self, index: usize) -> Self {
+        self.kind = Some(FailureKind::Lookup);
+        self.lookup = Some(index);
+        self
+    }
+
+    pub fn permutation(self) -> Self {
+        self.kind(FailureKind::Permutation)
+    }
+
+    pub fn in_region(mut self, name: &str) -> Self {
+        self.region = Some(name.to_string());
+        self
+    }
+
+    pub fn at_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn at_row(mut self, row: usize) -> Self {
+        self.row = Some(row);
+        self
+    }
+
+    pub fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        fn check<T: PartialEq>(expected: &Option<T>, actual: &Option<T>) -> bool {
+            expected.is_none() || expected == actual
+        }
+
+        check(&self.kind, &Some(diagnostic.kind))
+            && check(&self.gate, &diagnostic.gate)
+            && check(&self.constraint, &diagnostic.constraint)
+            && check(&self.lookup, &diagnostic.lookup)
+            && check(&self.region, &diagnostic.region)
+            && check(&self.offset, &diagnostic.offset)
+            && check(&self.row, &diagnostic.row)
+    }
+
+    /// Panics unless one of the diagnostics of `report` matches.
+    #[track_caller]
+    pub fn assert(&self, report: &Report) {
+        assert!(
+            report.diagnostics.iter().any(|diagnostic| self.matches(diagnostic)),
+            "no failure matches {:?}, got:\n{}",
+            self,
+            report
+        );
+    }
+}
+
+fn column_prefix(kind: Any) -> &'static str {
+    match kind {
+        Any::Instance => "i",
+        Any::Advice => "a",
+        Any::Fixed => "f",
+    }
+}
+
+// The metadata of the failures only has private fields, so they are read back from their
+// `Display` forms.
+
+// "Region 1 ('Assign value')" or "Gate 0 ('range check')"
+fn parse_named(s: &str) -> Option<(usize, String)> {
+    let (_, rest) = s.split_once(' ')?;
+    let (index, name) = rest.split_once(' ').unwrap_or((rest, ""));
+    let name = name.strip_prefix("('").and_then(|name| name.strip_suffix("')"));
+    Some((index.parse().ok()?, name.unwrap_or_default().to_string()))
+        .filter(|(_, name)| !name.is_empty())
+}
+
+// "Column('Advice', 0)"
+fn parse_column(s: &str) -> Option<(Any, usize)> {
+    let (kind, index) = s.strip_prefix("Column('")?.strip_suffix(')')?.split_once("', ")?;
+    let kind = match kind {
+        "Instance" => Any::Instance,
+        "Advice" => Any::Advice,
+        "Fixed" => Any::Fixed,
+        _ => return None,
+    };
+    Some((kind, index.parse().ok()?))
+}
+
+// "Column('Advice', 0)@1", followed by the name of the cell if it has one
+fn parse_virtual_cell(s: &str) -> Option<(Any, usize, i32)> {
+    let (column, rest) = s.split_once(")@")?;
+    let (kind, index) = parse_column(&format!("{})", column))?;
+    let rotation: String = rest
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| c.is_ascii_digit() || (*i == 0 && *c == '-'))
+        .map(|(_, c)| c)
+        .collect();
+    Some((kind, index, rotation.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example1;
+    use crate::range_check::{example1 as range_check, example2 as lookup_range_check};
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use std::marker::PhantomData;
+
+    #[test]
+    fn test_constraint() {
+        let circuit = range_check::MyCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(8).into()),
+        };
+        let report = diagnose(&circuit, vec![]).unwrap();
+        expect_failure()
+            .constraint("range check")
+            .gate("range check")
+            .in_region("Assign value")
+            .at_offset(0)
+            .assert(&report);
+
+        let diagnostic = &report.diagnostics[0];
+        assert_eq!(diagnostic.row, Some(0));
+        assert_eq!(diagnostic.cells.len(), 1);
+        assert_eq!(diagnostic.cells[0].column, "a0");
+        assert_eq!(diagnostic.cells[0].value.as_deref(), Some("8"));
+        assert_eq!(diagnostic.cells[0].annotation.as_deref(), Some("value"));
+        assert_eq!(
+            report.to_string(),
+            "constraint \"range check\" of gate \"range check\" is not satisfied\n  \
+             at row 0, offset 0 of region \"Assign value\"\n  \
+             a0[0] = 8 \"value\"\n"
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let circuit = lookup_range_check::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(256).into()),
+        };
+        let report = diagnose(&circuit, vec![]).unwrap();
+        assert_eq!(report.diagnostics.len(), 1);
+        expect_failure()
+            .lookup(0)
+            .in_region("Assign value for lookup range check")
+            .assert(&report);
+
+        let cells = &report.diagnostics[0].cells;
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[0].value.as_deref(), Some("256"));
+    }
+
+    #[test]
+    fn test_permutation() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(56)]];
+        let report = diagnose(&example1::MyCircuit(PhantomData), instances).unwrap();
+        expect_failure().permutation().assert(&report);
+        assert!(!report.is_satisfied());
+    }
+
+    #[test]
+    fn test_satisfied() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let report = diagnose(&example1::MyCircuit(PhantomData), instances).unwrap();
+        assert!(report.is_satisfied());
+        assert_eq!(report.to_string(), "satisfied");
+    }
+
+    #[test]
+    #[should_panic(expected = "no failure matches")]
+    fn test_no_match() {
+        let circuit = range_check::MyCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(8).into()),
+        };
+        let report = diagnose(&circuit, vec![]).unwrap();
+        expect_failure().constraint("range check").in_region("Other").assert(&report);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_named("Region 1 ('Assign value')"), Some((1, "Assign value".into())));
+        assert_eq!(parse_named("Constraint 0"), None);
+        assert_eq!(parse_column("Column('Fixed', 2)"), Some((Any::Fixed, 2)));
+        assert_eq!(
+            parse_virtual_cell("Column('Advice', 0)@-1(value)"),
+            Some((Any::Advice, 0, -1))
+        );
+    }
+}
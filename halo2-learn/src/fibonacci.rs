@@ -1,3 +1,16 @@
-mod example1;
-mod example2;
-mod example3;
+pub mod compare;
+pub mod example1;
+pub mod example2;
+pub mod example3;
+pub mod example4;
+pub mod example5;
+pub mod example6;
+pub mod example7;
+pub mod example8;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::AssignedCell;
+
+/// An assigned Fibonacci term, shared by the `FiboChip`s of example1 and example2.
+#[derive(Debug, Clone)]
+pub struct ACell<F: PrimeField>(pub AssignedCell<F, F>);
@@ -0,0 +1,386 @@
+//! The same Fibonacci term proven with three layouts, and what each one costs:
+//!
+//! - `rows`: the `FiboChip` of example1, three columns and one region per step, each
+//!   step copying its inputs from the previous one,
+//! - `column`: the `FiboChip` of example2, one column and a single region, where the gate
+//!   reaches two rows ahead with `Rotation(2)`,
+//! - `matrix`: example5, about `2 log2(n)` rows of squarings of the Fibonacci matrix,
+//! - `doubling`: example6, fast doubling over the 64 bits of a public n, i.e. a constant
+//!   number of rows and the same keys for every n.
+//!
+//! ```text
+//! $ cargo run --release --bin halo2-learn -- compare --n 1000 --prove
+//! F(1000)    rows  advice  fixed  instance  equality  copies  degree   k  proof size  prover
+//! rows        999       3      0         1         4    1999       3  10  ...
+//! column     1001       1      0         1         2       3       3  10  ...
+//! matrix       16       3      1         1         5       6       3   5  ...
+//! doubling     66       4      4         1         6      71       5   7  ...
+//! ```
+//!
+//! Each circuit proves `F(n)` for the public `[F(0), F(1), F(n)]` (`[F(0), F(1), n, F(n)]`
+//! for `doubling`), with F[0] = F[1] = 1 as in example1 and example2.
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::CircuitCost,
+    pasta::{Eq, Fp},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use super::{example1, example2, example4::fibonacci, example5, example6};
+use crate::{
+    prover::{Setup, Verification},
+    stats::CircuitStats,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Rows,
+    Column,
+    Matrix,
+    Doubling,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 4] = [Layout::Rows, Layout::Column, Layout::Matrix, Layout::Doubling];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Rows => "rows",
+            Layout::Column => "column",
+            Layout::Matrix => "matrix",
+            Layout::Doubling => "doubling",
+        }
+    }
+}
+
+/// `F(n)` with the `FiboChip` of example1: a first row, then one region per step.
+#[derive(Debug, Clone)]
+pub struct RowsCircuit<F> {
+    n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RowsCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        assert!(n >= 2, "the first row already computes F(2)");
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for RowsCircuit<F> {
+    type Config = example1::FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        example1::FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = example1::FiboChip::construct(config);
+
+        let (_, mut prev_b, mut prev_c) =
+            chip.assign_first_row(layouter.namespace(|| "first row"))?;
+        for _ in 3..=self.n {
+            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)
+    }
+}
+
+/// `F(n)` with the `FiboChip` of example2: `F(0)..=F(n)` in a single column.
+#[derive(Debug, Clone)]
+pub struct ColumnCircuit<F> {
+    n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ColumnCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        // the gate is enabled on the first two rows, and looks two rows ahead
+        assert!(n >= 3, "the table needs at least 4 rows");
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for ColumnCircuit<F> {
+    type Config = example2::FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        example2::FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = example2::FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), self.n + 1)?;
+        chip.expose_public(layouter.namespace(|| "out"), out, 2)
+    }
+}
+
+/// The public inputs of the linear and matrix layouts: `[F(0), F(1), F(n)]` with
+/// F[0] = F[1] = 1.
+pub fn instances(n: usize) -> Vec<Vec<Fp>> {
+    let (f0, f1) = (Fp::from(1), Fp::from(1));
+    vec![vec![f0, f1, fibonacci(n, f0, f1)]]
+}
+
+/// The costs of one layout.
+#[derive(Debug, Clone)]
+pub struct LayoutReport {
+    pub layout: Layout,
+    pub stats: CircuitStats,
+    /// The `k` everything is measured at, the smallest that fits.
+    pub k: u32,
+    /// In bytes, as estimated by `CircuitCost`.
+    pub proof_size: usize,
+    /// Time of `Setup::prove`, without the key generation. Only measured on request.
+    pub prover_time: Option<Duration>,
+}
+
+impl LayoutReport {
+    /// Measures `circuit`, and proves it when `prove` is set. A proof that does not verify
+    /// is an error.
+    pub fn new<C: Circuit<Fp> + Clone>(
+        layout: Layout,
+        circuit: &C,
+        instances: &[Vec<Fp>],
+        prove: bool,
+    ) -> Result<Self, Error> {
+        let stats = CircuitStats::new(circuit)?;
+        let k = stats.min_k;
+        let cost = CircuitCost::<Eq, _>::measure(k as usize, circuit);
+        // one circuit instance per proof
+        let proof_size = usize::from(cost.proof_size(1));
+
+        let prover_time = if prove {
+            let setup = Setup::new(k, circuit)?;
+            let start = Instant::now();
+            let proof = setup.prove(circuit.clone(), instances)?;
+            let elapsed = start.elapsed();
+            if let Verification::Rejected(e) = setup.verify(&proof, instances) {
+                return Err(e);
+            }
+            Some(elapsed)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            layout,
+            stats,
+            k,
+            proof_size,
+            prover_time,
+        })
+    }
+}
+
+/// The smallest `n` every layout can prove: the column layout needs F(0)..=F(3).
+pub const MIN_N: usize = 3;
+
+#[derive(Debug)]
+pub enum CompareError {
+    /// `n` is below [`MIN_N`].
+    TooSmall(usize),
+    Synthesis(Error),
+}
+
+impl From<Error> for CompareError {
+    fn from(e: Error) -> Self {
+        CompareError::Synthesis(e)
+    }
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompareError::TooSmall(n) => write!(f, "n = {} is below the minimum of {}", n, MIN_N),
+            CompareError::Synthesis(e) => write!(f, "synthesis failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CompareError {}
+
+/// The reports of every [`Layout`] for the same `n`, printed as a table.
+#[derive(Debug, Clone)]
+pub struct LayoutComparison {
+    pub n: usize,
+    pub reports: Vec<LayoutReport>,
+}
+
+impl LayoutComparison {
+    pub fn report(&self, layout: Layout) -> Option<&LayoutReport> {
+        self.reports.iter().find(|report| report.layout == layout)
+    }
+}
+
+impl fmt::Display for LayoutComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<8} {:>6}", format!("F({})", self.n), "rows")?;
+        write!(f, "  advice  fixed  instance  equality  copies  degree   k  proof size  prover")?;
+        for report in self.reports.iter() {
+            let stats = &report.stats;
+            let prover_time = match report.prover_time {
+                Some(time) => format!("{:.2?}", time),
+                None => "-".into(),
+            };
+            write!(
+                f,
+                "\n{:<8} {:>6} {:>7} {:>6} {:>9} {:>9} {:>7} {:>7} {:>3} {:>11}  {}",
+                report.layout.name(),
+                stats.rows,
+                stats.advice_columns,
+                stats.fixed_columns,
+                stats.instance_columns,
+                stats.permutation_columns,
+                stats.copies,
+                stats.degree,
+                report.k,
+                report.proof_size,
+                prover_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures every layout proving `F(n)`, and times their prover when `prove` is set.
+pub fn compare(n: usize, prove: bool) -> Result<LayoutComparison, CompareError> {
+    if n < MIN_N {
+        return Err(CompareError::TooSmall(n));
+    }
+
+    let instances = instances(n);
+    let reports = Layout::ALL
+        .into_iter()
+        .map(|layout| match layout {
+            Layout::Rows => LayoutReport::new(layout, &RowsCircuit::new(n), &instances, prove),
+            Layout::Column => {
+                LayoutReport::new(layout, &ColumnCircuit::new(n), &instances, prove)
+            }
+            Layout::Matrix => {
+                let circuit = example5::FibonacciCircuit::new(n);
+                LayoutReport::new(layout, &circuit, &instances, prove)
+            }
+            Layout::Doubling => {
+                let (f0, f1) = (instances[0][0], instances[0][1]);
+                let circuit = example6::FibonacciCircuit::new(n as u64);
+                LayoutReport::new(layout, &circuit, &example6::instances(n as u64, f0, f1), prove)
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(LayoutComparison { n, reports })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::min_k;
+    use halo2_proofs::dev::MockProver;
+
+    fn check<C: Circuit<Fp>>(circuit: &C, n: usize) {
+        let mut instances = instances(n);
+        MockProver::run(min_k(circuit).unwrap(), circuit, instances.clone())
+            .unwrap()
+            .assert_satisfied();
+
+        instances[0][2] += Fp::one();
+        let prover = MockProver::run(min_k(circuit).unwrap(), circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_layouts() {
+        for n in [3, 4, 9, 100] {
+            check(&RowsCircuit::new(n), n);
+            check(&ColumnCircuit::new(n), n);
+            check(&example5::FibonacciCircuit::new(n), n);
+        }
+        // F[9] = 55, like example1 and example2
+        assert_eq!(instances(9)[0][2], Fp::from(55));
+    }
+
+    #[test]
+    fn test_compare() {
+        let comparison = compare(1000, false).unwrap();
+        let stats = |layout| &comparison.report(layout).unwrap().stats;
+
+        // one row per step, F(0) and F(1) included for the column
+        assert_eq!(stats(Layout::Rows).rows, 999);
+        assert_eq!(stats(Layout::Column).rows, 1001);
+        // 1000 = 0b1111101000: M^1, 9 squarings, 5 steps and the output
+        assert_eq!(stats(Layout::Matrix).rows, 16);
+        // F(0), 64 doublings and the output, next to the running sum of n
+        assert_eq!(stats(Layout::Doubling).rows, 66);
+
+        // only the rows layout copies each term into the next region
+        assert_eq!(stats(Layout::Rows).copies, 2 + 2 * 998 + 1);
+        assert_eq!(stats(Layout::Column).copies, 3);
+        assert_eq!(stats(Layout::Column).advice_columns, 1);
+
+        // the squaring gate is the only one above degree 2
+        let gate_degree = |layout| stats(layout).gates.iter().map(|gate| gate.degree).max();
+        assert_eq!(gate_degree(Layout::Rows), Some(2));
+        assert_eq!(gate_degree(Layout::Matrix), Some(3));
+        assert!(comparison.reports.iter().all(|report| report.prover_time.is_none()));
+
+        let table = comparison.to_string();
+        assert!(table.starts_with("F(1000)"));
+        assert_eq!(table.lines().count(), 5);
+        assert!(table.lines().nth(1).unwrap().starts_with("rows        999"));
+    }
+
+    #[test]
+    fn test_compare_too_small() {
+        assert!(matches!(compare(2, false), Err(CompareError::TooSmall(2))));
+        assert!(compare(MIN_N, false).is_ok());
+    }
+
+    #[test]
+    fn prove_compare() {
+        let comparison = compare(16, true).unwrap();
+        assert!(comparison
+            .reports
+            .iter()
+            .all(|report| report.prover_time.is_some() && report.proof_size > 0));
+    }
+}
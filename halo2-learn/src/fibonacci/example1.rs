@@ -2,18 +2,17 @@ use std::marker::PhantomData;
 use ff::{Field, PrimeField};
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 
-#[derive(Debug, Clone)]
-struct ACell<F: PrimeField>(AssignedCell<F, F>);
+use super::ACell;
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
     pub instance: Column<Instance>,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: PrimeField> {
+pub struct FiboChip<F: PrimeField> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
@@ -143,7 +142,7 @@ impl<F: PrimeField> FiboChip<F> {
 }
 
 #[derive(Default)]
-struct MyCircuit<F> (PhantomData<F>);
+pub struct MyCircuit<F> (pub PhantomData<F>);
 
 impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
     type Config = FiboConfig;
@@ -190,18 +189,18 @@ impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
 #[cfg(test)]
 mod tests {
     use super::MyCircuit;
+    use crate::stats::min_k;
     use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
     use std::marker::PhantomData;
 
     #[test]
     fn test_example1() {
-        let k = 4;
-
         let a = Fp::from(1); // F[0]
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
         let circuit = MyCircuit(PhantomData);
+        let k = min_k(&circuit).unwrap();
 
         let mut public_input = vec![a, b, out];
 
@@ -218,15 +217,11 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("fib-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 1 Layout", ("sans-serif", 60)).unwrap();
-
+        let options = crate::layout::LayoutOptions {
+            title: Some("Fib 1 Layout".into()),
+            ..Default::default()
+        };
         let circuit = MyCircuit::<Fp>(PhantomData);
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "fib-1-layout.png", &options).unwrap();
     }
 }
\ No newline at end of file
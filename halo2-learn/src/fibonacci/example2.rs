@@ -2,18 +2,17 @@ use std::marker::PhantomData;
 use ff::{Field, PrimeField};
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 
-#[derive(Debug, Clone)]
-struct ACell<F: PrimeField>(AssignedCell<F, F>);
+use super::ACell;
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
-    advice: Column<Advice>,
-    selector: Selector,
-    instance: Column<Instance>,
+pub struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: PrimeField> {
+pub struct FiboChip<F: PrimeField> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
@@ -124,53 +123,53 @@ impl<F: PrimeField> FiboChip<F> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
-
-    #[derive(Default)]
-    struct MyCircuit<F>(PhantomData<F>);
+#[derive(Default)]
+pub struct MyCircuit<F>(pub PhantomData<F>);
 
-    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
-        type Config = FiboConfig;
-        type FloorPlanner = SimpleFloorPlanner;
+impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let advice = meta.advice_column();
-            let instance = meta.instance_column();
-            FiboChip::configure(meta, advice, instance)
-        }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            let chip = FiboChip::construct(config);
-            
-            // entire table's last cell is the output of 10 times Fibonacci.
-            let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        
+        // entire table's last cell is the output of 10 times Fibonacci.
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
 
-            chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
 
-            Ok(())
-        }
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::min_k;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     #[test]
     fn test_example2() {
-        let k = 4;
-
         let a = Fp::from(1); // F[0]
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
         let circuit = MyCircuit(PhantomData);
+        let k = min_k(&circuit).unwrap();
 
         let mut public_input = vec![a, b, out];
 
@@ -187,14 +186,11 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo2() {
-        use plotters::prelude::*;
-        let root = BitMapBackend::new("fib-2-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 2 Layout", ("sans-serif", 60)).unwrap();
-
+        let options = crate::layout::LayoutOptions {
+            title: Some("Fib 2 Layout".into()),
+            ..Default::default()
+        };
         let circuit = MyCircuit::<Fp>(PhantomData);
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "fib-2-layout.png", &options).unwrap();
     }
 }
\ No newline at end of file
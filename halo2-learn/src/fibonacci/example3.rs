@@ -7,17 +7,17 @@ use halo2_proofs::{
 };
 
 #[derive(Debug, Clone)]
-struct FunctionConfig<F: PrimeField> {
-    selector: Selector,
-    a: Column<Advice>,
-    b: Column<Advice>,
-    c: Column<Advice>,
-    a_equals_b: IsZeroConfig<F>,
-    output: Column<Advice>,
+pub struct FunctionConfig<F: PrimeField> {
+    pub selector: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub a_equals_b: IsZeroConfig<F>,
+    pub output: Column<Advice>,
 }
 
 #[derive(Debug, Clone)]
-struct FunctionChip<F: PrimeField> {
+pub struct FunctionChip<F: PrimeField> {
     config: FunctionConfig<F>,
 }
 
@@ -96,10 +96,10 @@ impl<F: PrimeField> FunctionChip<F> {
 }
 
 #[derive(Default)]
-struct FunctionCircuit<F> {
-    a: F,
-    b: F,
-    c: F,
+pub struct FunctionCircuit<F> {
+    pub a: F,
+    pub b: F,
+    pub c: F,
 }
 
 impl<F: PrimeField> Circuit<F> for FunctionCircuit<F> {
@@ -124,6 +124,7 @@ impl<F: PrimeField> Circuit<F> for FunctionCircuit<F> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::stats::min_k;
     use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     #[test]
@@ -134,7 +135,7 @@ mod tests {
             c: Fp::from(15),
         };
 
-        let prover = MockProver::run(4, &circuit, vec![]).unwrap();
+        let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
         prover.assert_satisfied();
     }
 
@@ -142,26 +143,15 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibo3() {
-        use plotters::prelude::*;
-        let root = BitMapBackend::new("fib-3-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 3 Layout", ("sans-serif", 60)).unwrap();
-
-        // let circuit = MyCircuit::<Fp> {
-        //     a: Value::unknown(),
-        //     b: Value::unknown(),
-        // };
-        // halo2_proofs::dev::CircuitLayout::default()
-        //     .render(4, &circuit, &root)
-        //     .unwrap();
-
-        let circuit = FunctionCircuit::<Fp>{
+        let options = crate::layout::LayoutOptions {
+            title: Some("Fib 3 Layout".into()),
+            ..Default::default()
+        };
+        let circuit = FunctionCircuit::<Fp> {
             a: Fp::ZERO,
             b: Fp::ZERO,
             c: Fp::ZERO,
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "fib-3-layout.png", &options).unwrap();
     }
 }
@@ -0,0 +1,287 @@
+use std::marker::PhantomData;
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::ACell;
+
+// Same single-column layout as example2, but the number of steps is a parameter of the
+// circuit instead of being hard-coded, and an index column counts the steps so that
+// `n` itself can be exposed as a public input.
+//
+//   index | advice | q_index | q_fib
+//  -------+--------+---------+-------
+//     0   |  F(0)  |    1    |   1
+//     1   |  F(1)  |    1    |   1
+//    ...  |  ...   |   ...   |  ...
+//   n-1   | F(n-1) |    1    |   0
+//     n   |  F(n)  |    0    |   0
+//
+// Public inputs: [F(0), F(1), n, F(n)]
+
+/// Row of each public input in the instance column.
+pub const F0_ROW: usize = 0;
+pub const F1_ROW: usize = 1;
+pub const N_ROW: usize = 2;
+pub const OUT_ROW: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig {
+    pub index: Column<Advice>,
+    pub advice: Column<Advice>,
+    pub constant: Column<Fixed>,
+    pub q_index: Selector,
+    pub q_fib: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboChip<F: PrimeField> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        index: Column<Advice>,
+        advice: Column<Advice>,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let q_index = meta.selector();
+        let q_fib = meta.selector();
+
+        meta.enable_equality(index);
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        // the index starts from the constant 0
+        meta.enable_constant(constant);
+
+        meta.create_gate("index", |meta| {
+            let s = meta.query_selector(q_index);
+            let cur = meta.query_advice(index, Rotation::cur());
+            let next = meta.query_advice(index, Rotation::next());
+            vec![s * (cur + Expression::Constant(F::ONE) - next)]
+        });
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_fib);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig {
+            index,
+            advice,
+            constant,
+            q_index,
+            q_fib,
+            instance,
+        }
+    }
+
+    /// Assigns `F(0)..=F(n)`, taking `F(0)` and `F(1)` from the instance column.
+    /// Returns the cells of `n` and `F(n)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: usize,
+    ) -> Result<(ACell<F>, ACell<F>), Error> {
+        assert!(n >= 1, "the table needs at least F(0) and F(1)");
+
+        layouter.assign_region(
+            || "fibonacci table",
+            |mut region| {
+                let mut index_cell = region
+                    .assign_advice_from_constant(|| "index 0", self.config.index, 0, F::ZERO)
+                    .map(ACell)?;
+                for row in 1..=n {
+                    self.config.q_index.enable(&mut region, row - 1)?;
+                    index_cell = region
+                        .assign_advice(
+                            || format!("index {}", row),
+                            self.config.index,
+                            row,
+                            || Value::known(F::from(row as u64)),
+                        )
+                        .map(ACell)?;
+                }
+
+                let mut a_cell = region
+                    .assign_advice_from_instance(
+                        || "F(0)",
+                        self.config.instance,
+                        F0_ROW,
+                        self.config.advice,
+                        0,
+                    )
+                    .map(ACell)?;
+                let mut b_cell = region
+                    .assign_advice_from_instance(
+                        || "F(1)",
+                        self.config.instance,
+                        F1_ROW,
+                        self.config.advice,
+                        1,
+                    )
+                    .map(ACell)?;
+
+                for row in 2..=n {
+                    self.config.q_fib.enable(&mut region, row - 2)?;
+                    let c_cell = region
+                        .assign_advice(
+                            || format!("F({})", row),
+                            self.config.advice,
+                            row,
+                            || a_cell.0.value().copied() + b_cell.0.value(),
+                        )
+                        .map(ACell)?;
+                    a_cell = b_cell;
+                    b_cell = c_cell;
+                }
+
+                Ok((index_cell, b_cell))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves `F(n)` for the Fibonacci sequence starting at the public `F(0)`, `F(1)`.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F> {
+    n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> FibonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        assert!(n >= 1, "the table needs at least F(0) and F(1)");
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The smallest `k` whose `2^k` rows fit the `n + 1` table rows plus the rows
+    /// reserved for blinding factors.
+    pub fn min_k(&self) -> Result<u32, Error> {
+        crate::stats::min_k(self)
+    }
+
+    /// The public inputs `[F(0), F(1), n, F(n)]` for the given starting values.
+    pub fn instances(&self, f0: F, f1: F) -> Vec<Vec<F>> {
+        vec![vec![f0, f1, F::from(self.n as u64), fibonacci(self.n, f0, f1)]]
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let index = meta.advice_column();
+        let advice = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, index, advice, constant, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+
+        let (n_cell, out_cell) = chip.assign(layouter.namespace(|| "table"), self.n)?;
+
+        chip.expose_public(layouter.namespace(|| "n"), &n_cell, N_ROW)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out_cell, OUT_ROW)?;
+
+        Ok(())
+    }
+}
+
+/// Native reference: `F(n)` in the field, starting from `F(0) = f0`, `F(1) = f1`.
+pub fn fibonacci<F: PrimeField>(n: usize, f0: F, f1: F) -> F {
+    let (mut a, mut b) = (f0, f1);
+    for _ in 0..n {
+        let c = a + b;
+        a = b;
+        b = c;
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_example4() {
+        for n in [1, 2, 3, 9, 100, 1000, 3000] {
+            for (f0, f1) in [(Fp::from(1), Fp::from(1)), (Fp::from(3), Fp::from(7))] {
+                let circuit = FibonacciCircuit::<Fp>::new(n);
+                let k = circuit.min_k().unwrap();
+                let instances = circuit.instances(f0, f1);
+
+                let prover = MockProver::run(k, &circuit, instances.clone()).unwrap();
+                prover.assert_satisfied();
+
+                // wrong F(n)
+                let mut wrong_out = instances.clone();
+                wrong_out[0][OUT_ROW] += Fp::one();
+                let prover = MockProver::run(k, &circuit, wrong_out).unwrap();
+                assert!(prover.verify().is_err());
+
+                // wrong n
+                let mut wrong_n = instances;
+                wrong_n[0][N_ROW] += Fp::one();
+                let prover = MockProver::run(k, &circuit, wrong_n).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn native_fibonacci() {
+        // example1 and example2 prove F(9) = 55
+        assert_eq!(fibonacci(9, Fp::from(1), Fp::from(1)), Fp::from(55));
+
+        let mut prev = (0u128, 1u128);
+        for n in 0..150 {
+            assert_eq!(fibonacci(n, Fp::zero(), Fp::one()), Fp::from_u128(prev.0));
+            prev = (prev.1, prev.0 + prev.1);
+        }
+    }
+
+    #[test]
+    fn min_k() {
+        assert_eq!(FibonacciCircuit::<Fp>::new(9).min_k().unwrap(), 4);
+        assert_eq!(FibonacciCircuit::<Fp>::new(1000).min_k().unwrap(), 10);
+        assert_eq!(FibonacciCircuit::<Fp>::new(1020).min_k().unwrap(), 11);
+    }
+}
@@ -0,0 +1,330 @@
+use std::marker::PhantomData;
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::{example4::fibonacci, ACell};
+
+// F(n) by repeated squaring of the Fibonacci matrix instead of one row per step:
+//
+//   M = | 1 1 |      M^k = | F(k+1) F(k)   |      (standard sequence, F(0) = 0, F(1) = 1)
+//       | 1 0 |            | F(k)   F(k-1) |
+//
+// M^k is symmetric, so each row holds (p, q, r) = (F(k+1), F(k), F(k-1)). Going over the
+// bits of n from the top, M^k -> M^2k squares the matrix and M^k -> M^(k+1) multiplies it
+// by M:
+//
+//   square:  p' = p^2 + q^2,  q' = q * (p + r),  r' = q^2 + r^2
+//   step:    p' = p + q,      q' = p,            r' = q
+//
+// The sequence starting at the public G(0), G(1) is G(n) = G(0) * F(n-1) + G(1) * F(n),
+// checked on the last row. For n = 9 = 0b1001:
+//
+//      p  |  q  |  r  | q_square | q_step | q_out
+//   ------+-----+-----+----------+--------+-------
+//      1  |  1  |  0  |    1     |   0    |   0      M^1, constants
+//      2  |  1  |  1  |    1     |   0    |   0      M^2
+//      5  |  3  |  2  |    1     |   0    |   0      M^4
+//     34  | 21  | 13  |    0     |   1    |   0      M^8
+//     55  | 34  | 21  |    0     |   0    |   1      M^9
+//    G(0) | G(1)| G(9)|    0     |   0    |   0      from the instance column
+//
+// The bits of n are fixed by the circuit, so each n has its own keys. Public inputs:
+// [G(0), G(1), G(n)], like example1 and example2.
+
+/// Row of each public input in the instance column.
+pub const F0_ROW: usize = 0;
+pub const F1_ROW: usize = 1;
+pub const OUT_ROW: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub constant: Column<Fixed>,
+    pub q_square: Selector,
+    pub q_step: Selector,
+    pub q_out: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboChip<F: PrimeField> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let q_square = meta.selector();
+        let q_step = meta.selector();
+        let q_out = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        // M^1 is made of constants
+        meta.enable_constant(constant);
+
+        let [p, q, r] = advice;
+
+        meta.create_gate("square", |meta| {
+            let s = meta.query_selector(q_square);
+            let p0 = meta.query_advice(p, Rotation::cur());
+            let q0 = meta.query_advice(q, Rotation::cur());
+            let r0 = meta.query_advice(r, Rotation::cur());
+            let p1 = meta.query_advice(p, Rotation::next());
+            let q1 = meta.query_advice(q, Rotation::next());
+            let r1 = meta.query_advice(r, Rotation::next());
+            Constraints::with_selector(
+                s,
+                [
+                    ("p", p0.clone() * p0.clone() + q0.clone() * q0.clone() - p1),
+                    ("q", q0.clone() * (p0 + r0.clone()) - q1),
+                    ("r", q0.clone() * q0 + r0.clone() * r0 - r1),
+                ],
+            )
+        });
+
+        meta.create_gate("step", |meta| {
+            let s = meta.query_selector(q_step);
+            let p0 = meta.query_advice(p, Rotation::cur());
+            let q0 = meta.query_advice(q, Rotation::cur());
+            let p1 = meta.query_advice(p, Rotation::next());
+            let q1 = meta.query_advice(q, Rotation::next());
+            let r1 = meta.query_advice(r, Rotation::next());
+            Constraints::with_selector(
+                s,
+                [
+                    ("p", p0.clone() + q0.clone() - p1),
+                    ("q", p0 - q1),
+                    ("r", q0 - r1),
+                ],
+            )
+        });
+
+        meta.create_gate("out", |meta| {
+            // G(n) = G(0) * F(n-1) + G(1) * F(n), with G(0), G(1), G(n) on the next row
+            let s = meta.query_selector(q_out);
+            let fn0 = meta.query_advice(q, Rotation::cur());
+            let fn_prev = meta.query_advice(r, Rotation::cur());
+            let g0 = meta.query_advice(p, Rotation::next());
+            let g1 = meta.query_advice(q, Rotation::next());
+            let out = meta.query_advice(r, Rotation::next());
+            vec![s * (g0 * fn_prev + g1 * fn0 - out)]
+        });
+
+        FiboConfig {
+            advice,
+            constant,
+            q_square,
+            q_step,
+            q_out,
+            instance,
+        }
+    }
+
+    /// Assigns `M^1, ..., M^n` by squarings and steps, then `G(0)` and `G(1)` from the
+    /// instance column and `G(n)`. Returns the cell of `G(n)`.
+    pub fn assign(&self, mut layouter: impl Layouter<F>, n: usize) -> Result<ACell<F>, Error> {
+        assert!(n >= 1, "M^0 has no row");
+
+        layouter.assign_region(
+            || "matrix powers",
+            |mut region| {
+                let [p, q, r] = self.config.advice;
+
+                let m = [F::ONE, F::ONE, F::ZERO];
+                let names = ["F(2)", "F(1)", "F(0)"];
+                for ((column, value), name) in [p, q, r].into_iter().zip(m).zip(names) {
+                    region.assign_advice_from_constant(|| name, column, 0, value)?;
+                }
+                let mut state = m.map(Value::known);
+
+                let mut row = 0;
+                let mut power = 1;
+                let bits = usize::BITS - n.leading_zeros();
+                for i in (0..bits - 1).rev() {
+                    self.config.q_square.enable(&mut region, row)?;
+                    let [p0, q0, r0] = state;
+                    state = [
+                        p0 * p0 + q0 * q0,
+                        q0 * (p0 + r0),
+                        q0 * q0 + r0 * r0,
+                    ];
+                    power *= 2;
+                    row += 1;
+                    self.assign_state(&mut region, row, power, state)?;
+
+                    if (n >> i) & 1 == 1 {
+                        self.config.q_step.enable(&mut region, row)?;
+                        let [p0, q0, _] = state;
+                        state = [p0 + q0, p0, q0];
+                        power += 1;
+                        row += 1;
+                        self.assign_state(&mut region, row, power, state)?;
+                    }
+                }
+
+                self.config.q_out.enable(&mut region, row)?;
+                let g0 = region.assign_advice_from_instance(
+                    || "G(0)",
+                    self.config.instance,
+                    F0_ROW,
+                    p,
+                    row + 1,
+                )?;
+                let g1 = region.assign_advice_from_instance(
+                    || "G(1)",
+                    self.config.instance,
+                    F1_ROW,
+                    q,
+                    row + 1,
+                )?;
+                let [_, fn0, fn_prev] = state;
+                region
+                    .assign_advice(
+                        || format!("G({})", n),
+                        r,
+                        row + 1,
+                        || g0.value().copied() * fn_prev + g1.value().copied() * fn0,
+                    )
+                    .map(ACell)
+            },
+        )
+    }
+
+    // Assigns M^power on `row`.
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        power: usize,
+        state: [Value<F>; 3],
+    ) -> Result<(), Error> {
+        let names = [power + 1, power, power - 1];
+        for ((column, value), k) in self.config.advice.into_iter().zip(state).zip(names) {
+            region.assign_advice(|| format!("F({})", k), column, row, || value)?;
+        }
+        Ok(())
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves `G(n)` for the sequence starting at the public `G(0)`, `G(1)` in about
+/// `2 log2(n)` rows.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F> {
+    n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> FibonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        assert!(n >= 1, "M^0 has no row");
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The public inputs `[G(0), G(1), G(n)]` for the given starting values.
+    pub fn instances(&self, f0: F, f1: F) -> Vec<Vec<F>> {
+        vec![vec![f0, f1, fibonacci(self.n, f0, f1)]]
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, constant, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "powers"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, OUT_ROW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::{min_k, CircuitStats};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_example5() {
+        for n in [1, 2, 3, 9, 100, 1000, (1 << 16) - 1, 1 << 16] {
+            for (f0, f1) in [(Fp::from(1), Fp::from(1)), (Fp::from(3), Fp::from(7))] {
+                let circuit = FibonacciCircuit::<Fp>::new(n);
+                let k = min_k(&circuit).unwrap();
+                let mut instances = circuit.instances(f0, f1);
+
+                let prover = MockProver::run(k, &circuit, instances.clone()).unwrap();
+                prover.assert_satisfied();
+
+                instances[0][OUT_ROW] += Fp::one();
+                let prover = MockProver::run(k, &circuit, instances).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_as_example1() {
+        // F[9] = 55, like example1 and example2
+        let circuit = FibonacciCircuit::<Fp>::new(9);
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        assert_eq!(circuit.instances(Fp::from(1), Fp::from(1)), instances);
+        MockProver::run(min_k(&circuit).unwrap(), &circuit, instances)
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_rows() {
+        // one square per bit after the first, one step per other set bit, and the output
+        let rows = |n| CircuitStats::new(&FibonacciCircuit::<Fp>::new(n)).unwrap().rows;
+        assert_eq!(rows(1), 2);
+        assert_eq!(rows(9), 6);
+        assert_eq!(rows(1 << 20), 22);
+        assert_eq!(rows((1 << 20) - 1), 40);
+    }
+}
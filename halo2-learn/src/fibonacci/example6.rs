@@ -0,0 +1,378 @@
+use std::marker::PhantomData;
+use ff::{Field, PrimeField, PrimeFieldBits};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::ACell;
+use crate::range_check::decompose_range_check::DecomposeRangeCheckConfig;
+
+// F(n) for a public n < 2^64 in O(log n) rows, with the fast-doubling identities of the
+// standard sequence (F(0) = 0, F(1) = 1):
+//
+//   F(2k)     = F(k) * (2 F(k+1) - F(k))
+//   F(2k + 1) = F(k)^2 + F(k+1)^2
+//
+// n is split into bits by the running sum of `DecomposeRangeCheckConfig` with 1-bit
+// windows, so z_i = n >> i and z_64 = 0. The running sum is copied in reverse next to
+// (F(z), F(z + 1)), and each row goes from k = z_{i+1} to z_i = 2k + bit, where the bit
+// is z_i - 2 z_{i+1}:
+//
+//   bit = 0:  (F(k), F(k+1)) -> (F(2k), F(2k+1))
+//   bit = 1:  (F(k), F(k+1)) -> (F(2k+1), F(2k) + F(2k+1))
+//
+// The sequence starting at the public G(0), G(1) is
+// G(n) = G(0) * F(n-1) + G(1) * F(n) = G(0) * F(n+1) + (G(1) - G(0)) * F(n),
+// checked on the last row. For n = 6 = 0b110:
+//
+//      z   |   a   |   b   | q_double | q_out
+//   -------+-------+-------+----------+-------
+//    z_64  |   0   |   1   |    1     |   0      F(0), F(1), constants
+//    ...   |  ...  |  ...  |   ...    |  ...     z = 0
+//    z_3   |   0   |   1   |    1     |   0
+//    z_2   |   1   |   1   |    1     |   0      F(1), F(2)
+//    z_1   |   2   |   3   |    1     |   0      F(3), F(4)
+//    z_0   |   8   |  13   |    0     |   1      F(6), F(7), z_0 = n
+//    G(0)  |  G(1) |  G(n) |    0     |   0      from the instance column
+//
+// Unlike example5, n is a public input and not part of the circuit: the same keys prove
+// every n. Public inputs: [G(0), G(1), n, G(n)], like example4.
+
+/// Row of each public input in the instance column.
+pub const F0_ROW: usize = 0;
+pub const F1_ROW: usize = 1;
+pub const N_ROW: usize = 2;
+pub const OUT_ROW: usize = 3;
+
+/// Bits of n, which must be below `2^N_BITS`.
+pub const N_BITS: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig<F: PrimeFieldBits> {
+    /// `z`, `a` and `b` in the table above.
+    pub advice: [Column<Advice>; 3],
+    pub constant: Column<Fixed>,
+    pub q_double: Selector,
+    pub q_out: Selector,
+    pub instance: Column<Instance>,
+    /// Splits n into 1-bit windows.
+    pub bits: DecomposeRangeCheckConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboChip<F: PrimeFieldBits> {
+    config: FiboConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> FiboChip<F> {
+    pub fn construct(config: FiboConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        running_sum: Column<Advice>,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> FiboConfig<F> {
+        let q_double = meta.selector();
+        let q_out = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        // F(0) and F(1) are constants
+        meta.enable_constant(constant);
+
+        let bits = DecomposeRangeCheckConfig::configure(meta, running_sum, 1);
+
+        let [z, a, b] = advice;
+
+        meta.create_gate("double", |meta| {
+            let s = meta.query_selector(q_double);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let b_cur = meta.query_advice(b, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+
+            let two = Expression::Constant(F::from(2));
+            // boolean, as it is looked up by the decomposition
+            let bit = z_next - z_cur * two.clone();
+            // F(2k) and F(2k + 1)
+            let even = a_cur.clone() * (b_cur.clone() * two - a_cur.clone());
+            let odd = a_cur.clone() * a_cur + b_cur.clone() * b_cur;
+
+            Constraints::with_selector(
+                s,
+                [
+                    (
+                        "a",
+                        even.clone() + bit.clone() * (odd.clone() - even.clone()) - a_next,
+                    ),
+                    ("b", odd + bit * even - b_next),
+                ],
+            )
+        });
+
+        meta.create_gate("out", |meta| {
+            // G(n) = G(0) * F(n+1) + (G(1) - G(0)) * F(n), with G(0), G(1), G(n) on the
+            // next row
+            let s = meta.query_selector(q_out);
+            let fib_n = meta.query_advice(a, Rotation::cur());
+            let fib_next = meta.query_advice(b, Rotation::cur());
+            let g0 = meta.query_advice(z, Rotation::next());
+            let g1 = meta.query_advice(a, Rotation::next());
+            let out = meta.query_advice(b, Rotation::next());
+            vec![s * (g0.clone() * fib_next + (g1 - g0) * fib_n - out)]
+        });
+
+        FiboConfig {
+            advice,
+            constant,
+            q_double,
+            q_out,
+            instance,
+            bits,
+        }
+    }
+
+    /// Loads the 1-bit table of the decomposition, once per circuit.
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.bits.table.load(layouter)
+    }
+
+    /// Splits `n` into bits, then assigns `(F(z_i), F(z_i + 1))` from `z_64 = 0` up to
+    /// `z_0 = n`, and `G(0)`, `G(1)` from the instance column. Returns the cells of `n` and
+    /// `G(n)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: Value<F>,
+    ) -> Result<(ACell<F>, ACell<F>), Error> {
+        let zs = self
+            .config
+            .bits
+            .assign_running_sum(layouter.namespace(|| "bits of n"), n, N_BITS)?;
+
+        layouter.assign_region(
+            || "fast doubling",
+            |mut region| {
+                let [z, a, b] = self.config.advice;
+
+                let mut fib = Value::known((F::ZERO, F::ONE));
+                zs[N_BITS].copy_advice(|| "z_64", &mut region, z, 0)?;
+                region.assign_advice_from_constant(|| "F(0)", a, 0, F::ZERO)?;
+                region.assign_advice_from_constant(|| "F(1)", b, 0, F::ONE)?;
+
+                for row in 1..=N_BITS {
+                    let i = N_BITS - row;
+                    self.config.q_double.enable(&mut region, row - 1)?;
+
+                    let bit = zs[i]
+                        .value()
+                        .zip(zs[i + 1].value())
+                        .map(|(cur, prev)| *cur - prev.double() == F::ONE);
+                    fib = fib.zip(bit).map(|((fib_k, fib_next), bit)| {
+                        let even = fib_k * (fib_next.double() - fib_k);
+                        let odd = fib_k.square() + fib_next.square();
+                        if bit {
+                            (odd, even + odd)
+                        } else {
+                            (even, odd)
+                        }
+                    });
+
+                    zs[i].copy_advice(|| format!("z_{}", i), &mut region, z, row)?;
+                    region.assign_advice(|| "F(z)", a, row, || fib.map(|(a, _)| a))?;
+                    region.assign_advice(|| "F(z + 1)", b, row, || fib.map(|(_, b)| b))?;
+                }
+
+                self.config.q_out.enable(&mut region, N_BITS)?;
+                let out_row = N_BITS + 1;
+                let g0 = region.assign_advice_from_instance(
+                    || "G(0)",
+                    self.config.instance,
+                    F0_ROW,
+                    z,
+                    out_row,
+                )?;
+                let g1 = region.assign_advice_from_instance(
+                    || "G(1)",
+                    self.config.instance,
+                    F1_ROW,
+                    a,
+                    out_row,
+                )?;
+                let out = g0
+                    .value()
+                    .zip(g1.value())
+                    .zip(fib)
+                    .map(|((g0, g1), (fib_n, fib_next))| *g0 * fib_next + (*g1 - g0) * fib_n);
+                let out_cell = region.assign_advice(|| "G(n)", b, out_row, || out)?;
+
+                Ok((ACell(zs[0].clone()), ACell(out_cell)))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves `G(n)` for the public `G(0)`, `G(1)` and `n < 2^64`, with the same keys for
+/// every `n`.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F> {
+    pub n: Value<F>,
+}
+
+impl<F: PrimeFieldBits> FibonacciCircuit<F> {
+    pub fn new(n: u64) -> Self {
+        Self {
+            n: Value::known(F::from(n)),
+        }
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            n: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let running_sum = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, running_sum, constant, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let (n_cell, out_cell) = chip.assign(layouter.namespace(|| "fibonacci"), self.n)?;
+
+        chip.expose_public(layouter.namespace(|| "n"), &n_cell, N_ROW)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out_cell, OUT_ROW)
+    }
+}
+
+/// Native reference by fast doubling: `G(n)` in the field, starting from `G(0) = f0`,
+/// `G(1) = f1`.
+pub fn fibonacci<F: Field>(n: u64, f0: F, f1: F) -> F {
+    // (F(k), F(k + 1)) of the standard sequence, from the top bit of n down
+    let (mut a, mut b) = (F::ZERO, F::ONE);
+    for i in (0..u64::BITS).rev() {
+        let even = a * (b.double() - a);
+        let odd = a.square() + b.square();
+        (a, b) = if (n >> i) & 1 == 1 {
+            (odd, even + odd)
+        } else {
+            (even, odd)
+        };
+    }
+    f0 * b + (f1 - f0) * a
+}
+
+/// The public inputs `[G(0), G(1), n, G(n)]`.
+pub fn instances<F: PrimeField>(n: u64, f0: F, f1: F) -> Vec<Vec<F>> {
+    vec![vec![f0, f1, F::from(n), fibonacci(n, f0, f1)]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{compare, example4};
+    use crate::stats::{min_k, CircuitStats};
+    use ff::PrimeField;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prover(circuit: &FibonacciCircuit<Fp>, instances: Vec<Vec<Fp>>) -> MockProver<Fp> {
+        MockProver::run(min_k(circuit).unwrap(), circuit, instances).unwrap()
+    }
+
+    #[test]
+    fn test_example6() {
+        for n in [0, 1, 2, 3, 6, 9, 100, 1 << 40, u64::MAX] {
+            for (f0, f1) in [(Fp::from(1), Fp::from(1)), (Fp::from(3), Fp::from(7))] {
+                let circuit = FibonacciCircuit::new(n);
+                let instances = instances(n, f0, f1);
+                prover(&circuit, instances.clone()).assert_satisfied();
+
+                // wrong G(n)
+                let mut wrong_out = instances.clone();
+                wrong_out[0][OUT_ROW] += Fp::one();
+                assert!(prover(&circuit, wrong_out).verify().is_err());
+
+                // G(n) of another n
+                let mut wrong_n = instances;
+                wrong_n[0][N_ROW] += Fp::one();
+                assert!(prover(&circuit, wrong_n).verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_n_too_large() {
+        // 2^64 does not fit the running sum, whatever G(n) is claimed
+        let n = Fp::from_u128(1 << 64);
+        let circuit = FibonacciCircuit { n: Value::known(n) };
+        let instances = vec![vec![Fp::from(1), Fp::from(1), n, Fp::from(1)]];
+        assert!(prover(&circuit, instances).verify().is_err());
+    }
+
+    #[test]
+    fn test_same_as_linear() {
+        let (f0, f1) = (Fp::from(1), Fp::from(1));
+        for n in 0..200 {
+            assert_eq!(fibonacci(n, f0, f1), example4::fibonacci(n as usize, f0, f1));
+        }
+
+        // example1 and example2 laid out for n terms prove the same output
+        for n in [3, 10, 33] {
+            let out = instances(n, f0, f1)[0][OUT_ROW];
+            let linear = vec![vec![f0, f1, out]];
+            assert_eq!(linear, compare::instances(n as usize));
+
+            let rows = compare::RowsCircuit::new(n as usize);
+            MockProver::run(min_k(&rows).unwrap(), &rows, linear.clone())
+                .unwrap()
+                .assert_satisfied();
+            let column = compare::ColumnCircuit::new(n as usize);
+            MockProver::run(min_k(&column).unwrap(), &column, linear)
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_same_keys() {
+        // the shape of the circuit does not depend on n
+        let small = CircuitStats::new(&FibonacciCircuit::<Fp>::new(1)).unwrap();
+        let large = CircuitStats::new(&FibonacciCircuit::<Fp>::new(u64::MAX)).unwrap();
+        assert_eq!((small.rows, small.copies), (large.rows, large.copies));
+        assert_eq!(small.rows, N_BITS + 2);
+    }
+}
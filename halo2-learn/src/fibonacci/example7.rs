@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+use ff::PrimeFieldBits;
+use halo2_proofs::{circuit::*, plonk::*};
+
+use super::{example1, ACell};
+use crate::range_check::chip::{RangeCheckChip, RangeCheckConfig};
+
+// The rows of example1, with every term range-checked to 64 bits by `RangeCheckChip`.
+//
+// Without the range checks, the terms are computed in the field and silently wrap around
+// p: the circuit proves F(n) mod p, not F(n). Here a, b < 2^64 so a + b < 2^65 < p, and
+// c = a + b holds over the integers; F(n) is the integer, and a sequence that leaves
+// 64 bits can't be proven at all.
+//
+//   a | b | c | selector          value | q_range_check
+//  ---+---+---+----------       --------+---------------
+//   1 | 1 | 2 |    1               1    |       1          a, b and c of each row are
+//   1 | 2 | 3 |    1               ...  |      ...         copied into the running sum
+//  ...                             1    |       1          of the range check
+//                                  ...  |      ...
+//
+// Public inputs: [F(0), F(1), F(n)], like example1. With F[0] = F[1] = 1, F[MAX_N] is the
+// last term below 2^64.
+
+/// Width of every term.
+pub const TERM_BITS: usize = 64;
+/// The largest `n` with F[0] = F[1] = 1: F[92] = 12200160415121876738.
+pub const MAX_N: usize = 92;
+
+const LOOKUP_BITS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig<F: PrimeFieldBits> {
+    pub fibo: example1::FiboConfig,
+    pub range: RangeCheckConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboChip<F: PrimeFieldBits> {
+    fibo: example1::FiboChip<F>,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeFieldBits> FiboChip<F> {
+    pub fn construct(config: FiboConfig<F>) -> Self {
+        Self {
+            fibo: example1::FiboChip::construct(config.fibo),
+            range: RangeCheckChip::construct(config.range),
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        value: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> FiboConfig<F> {
+        FiboConfig {
+            fibo: example1::FiboChip::configure(meta, advice, instance),
+            // 64 bits is always a running sum of 8-bit windows
+            range: RangeCheckChip::configure(meta, value, 0, LOOKUP_BITS),
+        }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    /// Assigns the rows of `F(0)..=F(n)` and range-checks each term. Returns the cell of
+    /// `F(n)`.
+    pub fn assign(&self, mut layouter: impl Layouter<F>, n: usize) -> Result<ACell<F>, Error> {
+        assert!(n >= 2, "the first row already computes F(2)");
+
+        let (a, mut prev_b, mut prev_c) =
+            self.fibo.assign_first_row(layouter.namespace(|| "first row"))?;
+        for cell in [&a, &prev_b, &prev_c] {
+            self.range.copy_check(layouter.namespace(|| "term"), &cell.0, TERM_BITS)?;
+        }
+
+        for _ in 3..=n {
+            let c = self.fibo.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            self.range.copy_check(layouter.namespace(|| "term"), &c.0, TERM_BITS)?;
+            prev_b = prev_c;
+            prev_c = c;
+        }
+
+        Ok(prev_c)
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.fibo.expose_public(layouter, cell, row)
+    }
+}
+
+/// Proves the integer `F(n)` for the public `F(0)`, `F(1)`, all terms below `2^64`.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F> {
+    n: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> FibonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        assert!(n >= 2, "the first row already computes F(2)");
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The public inputs `[F(0), F(1), F(n)]`, or `None` if a term does not fit in
+    /// [`TERM_BITS`] bits, i.e. there is no proof for this `n`.
+    pub fn instances(&self, f0: u64, f1: u64) -> Option<Vec<Vec<F>>> {
+        let out = fibonacci(self.n, f0, f1)?;
+        Some(vec![vec![F::from(f0), F::from(f1), F::from(out)]])
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let value = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, value, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        chip.load_table(&mut layouter)?;
+
+        let out = chip.assign(layouter.namespace(|| "terms"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)
+    }
+}
+
+/// Native reference over the integers: `F(n)` starting from `F(0) = f0`, `F(1) = f1`, or
+/// `None` if a term overflows `u64`.
+pub fn fibonacci(n: usize, f0: u64, f1: u64) -> Option<u64> {
+    if n == 0 {
+        return Some(f0);
+    }
+    let (mut a, mut b) = (f0, f1);
+    for _ in 1..n {
+        (a, b) = (b, a.checked_add(b)?);
+    }
+    Some(b)
+}
+
+/// The largest `n` whose terms all fit in `u64`, or `None` if the sequence never
+/// overflows, i.e. `f0 = f1 = 0`.
+pub fn max_n(f0: u64, f1: u64) -> Option<usize> {
+    if f0 == 0 && f1 == 0 {
+        return None;
+    }
+    // F(n) at least doubles every two terms, so this stops within about 130 terms
+    (1..).find(|&n| fibonacci(n + 1, f0, f1).is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example4;
+    use crate::stats::min_k;
+    use ff::PrimeField;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prover(n: usize, instances: Vec<Vec<Fp>>) -> MockProver<Fp> {
+        let circuit = FibonacciCircuit::<Fp>::new(n);
+        MockProver::run(min_k(&circuit).unwrap(), &circuit, instances).unwrap()
+    }
+
+    #[test]
+    fn test_example7() {
+        for n in [2, 3, 9, 50, MAX_N] {
+            let instances = FibonacciCircuit::<Fp>::new(n).instances(1, 1).unwrap();
+            prover(n, instances.clone()).assert_satisfied();
+
+            let mut wrong = instances;
+            wrong[0][2] += Fp::one();
+            assert!(prover(n, wrong).verify().is_err());
+        }
+        // F[9] = 55, like example1
+        let instances = FibonacciCircuit::<Fp>::new(9).instances(1, 1).unwrap();
+        assert_eq!(instances[0][2], Fp::from(55));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(max_n(1, 1), Some(MAX_N));
+        assert_eq!(fibonacci(MAX_N, 1, 1), Some(12200160415121876738));
+        assert_eq!(fibonacci(MAX_N + 1, 1, 1), None);
+        assert!(FibonacciCircuit::<Fp>::new(MAX_N + 1).instances(1, 1).is_none());
+        assert_eq!(max_n(0, 0), None);
+        // u64::MAX, 0, u64::MAX, u64::MAX
+        assert_eq!(max_n(u64::MAX, 0), Some(3));
+
+        // the field computes F[93] without complaint, but it is not a 64-bit value
+        let out = example4::fibonacci(MAX_N + 1, Fp::from(1), Fp::from(1));
+        assert_eq!(out, Fp::from_u128(19740274219868223167));
+        let instances = vec![vec![Fp::from(1), Fp::from(1), out]];
+        assert!(prover(MAX_N + 1, instances).verify().is_err());
+
+        // nor can the starting values be out of range
+        let f0 = Fp::from_u128(1 << 64);
+        let instances = vec![vec![f0, Fp::from(1), example4::fibonacci(5, f0, Fp::from(1))]];
+        assert!(prover(5, instances).verify().is_err());
+    }
+}
@@ -0,0 +1,373 @@
+use std::marker::PhantomData;
+use ff::PrimeFieldBits;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use super::{example1, ACell};
+use crate::range_check::chip::{RangeCheckChip, RangeCheckConfig};
+
+// F(n) mod m, on the rows of example1: its gate still checks a + b = c, and c is reduced
+// to c = q * m + r with a quotient q in {0, 1} and a remainder r < m. For m = 7:
+//
+//   a | b | c | q | r | d | m | selector | q_mod | q_less
+//  ---+---+---+---+---+---+---+----------+-------+--------
+//     |   |   |   | 1 | 5 | 7 |    0     |   0   |   1      F(0), from the instance column
+//     |   |   |   | 1 | 5 | 7 |    0     |   0   |   1      F(1)
+//   1 | 1 | 2 | 0 | 2 | 4 | 7 |    1     |   1   |   1      a and b copied from the r of
+//   ...                                                     the previous rows
+//   5 | 1 | 6 | 0 | 6 | 0 | 7 |    1     |   1   |   1
+//   1 | 6 | 7 | 1 | 0 | 6 | 7 |    1     |   1   |   1      1 + 6 = 1 * 7 + 0
+//
+// r < m is r + d = m - 1 with both r and d range-checked to the bits of m - 1, so that
+// neither can wrap around p. a and b are earlier remainders, so c = a + b < 2m and a
+// boolean quotient is enough.
+//
+// m is in a fixed column: it is part of the circuit, and each m has its own keys.
+// Public inputs: [F(0), F(1), F(n) mod m], with F(0), F(1) < m.
+
+/// Row of each public input in the instance column.
+pub const F0_ROW: usize = 0;
+pub const F1_ROW: usize = 1;
+pub const OUT_ROW: usize = 2;
+
+const MAX_POLY_BITS: usize = 2;
+const LOOKUP_BITS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct FiboConfig<F: PrimeFieldBits> {
+    pub fibo: example1::FiboConfig,
+    pub quotient: Column<Advice>,
+    pub remainder: Column<Advice>,
+    /// `m - 1 - r`
+    pub diff: Column<Advice>,
+    pub modulus: Column<Fixed>,
+    pub q_mod: Selector,
+    pub q_less: Selector,
+    pub range: RangeCheckConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FiboChip<F: PrimeFieldBits> {
+    config: FiboConfig<F>,
+    fibo: example1::FiboChip<F>,
+    range: RangeCheckChip<F>,
+    modulus: u64,
+}
+
+impl<F: PrimeFieldBits> FiboChip<F> {
+    pub fn construct(config: FiboConfig<F>, modulus: u64) -> Self {
+        assert!(modulus >= 2, "every value is 0 mod 1");
+        Self {
+            fibo: example1::FiboChip::construct(config.fibo.clone()),
+            range: RangeCheckChip::construct(config.range.clone()),
+            config,
+            modulus,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        quotient: Column<Advice>,
+        remainder: Column<Advice>,
+        diff: Column<Advice>,
+        value: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> FiboConfig<F> {
+        let fibo = example1::FiboChip::configure(meta, advice, instance);
+        let modulus = meta.fixed_column();
+        let q_mod = meta.selector();
+        let q_less = meta.selector();
+
+        meta.enable_equality(remainder);
+        meta.enable_equality(diff);
+
+        let c = advice[2];
+
+        meta.create_gate("mod m", |meta| {
+            let s = meta.query_selector(q_mod);
+            let c = meta.query_advice(c, Rotation::cur());
+            let q = meta.query_advice(quotient, Rotation::cur());
+            let r = meta.query_advice(remainder, Rotation::cur());
+            let m = meta.query_fixed(modulus, Rotation::cur());
+            Constraints::with_selector(
+                s,
+                [
+                    ("quotient is boolean", q.clone() * (Expression::Constant(F::ONE) - q.clone())),
+                    ("c = q * m + r", c - q * m - r),
+                ],
+            )
+        });
+
+        meta.create_gate("less than m", |meta| {
+            let s = meta.query_selector(q_less);
+            let r = meta.query_advice(remainder, Rotation::cur());
+            let d = meta.query_advice(diff, Rotation::cur());
+            let m = meta.query_fixed(modulus, Rotation::cur());
+            vec![s * (r + d + Expression::Constant(F::ONE) - m)]
+        });
+
+        FiboConfig {
+            fibo,
+            quotient,
+            remainder,
+            diff,
+            modulus,
+            q_mod,
+            q_less,
+            range: RangeCheckChip::configure(meta, value, MAX_POLY_BITS, LOOKUP_BITS),
+        }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    /// The bits of `m - 1`, to which the remainders are range-checked.
+    pub fn num_bits(&self) -> usize {
+        (u64::BITS - (self.modulus - 1).leading_zeros()) as usize
+    }
+
+    /// Copies `F(0)` and `F(1)` from the instance column, and checks that they are below m.
+    pub fn assign_first_rows(
+        &self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(ACell<F>, ACell<F>), Error> {
+        let (f0, d0, f1, d1) = layouter.assign_region(
+            || "first rows",
+            |mut region| {
+                let mut cells = vec![];
+                for (row, instance_row) in [F0_ROW, F1_ROW].into_iter().enumerate() {
+                    let r = region.assign_advice_from_instance(
+                        || format!("F({})", instance_row),
+                        self.config.fibo.instance,
+                        instance_row,
+                        self.config.remainder,
+                        row,
+                    )?;
+                    let d = self.assign_less(&mut region, row, r.value().copied())?;
+                    cells.push((r, d));
+                }
+                let [(f0, d0), (f1, d1)]: [_; 2] = cells.try_into().unwrap();
+                Ok((f0, d0, f1, d1))
+            },
+        )?;
+
+        for cell in [&f0, &d0, &f1, &d1] {
+            self.range.copy_check(layouter.namespace(|| "below m"), cell, self.num_bits())?;
+        }
+        Ok((ACell(f0), ACell(f1)))
+    }
+
+    /// Assigns `c = a + b` and its remainder `r = c mod m` for the previous two remainders.
+    /// Returns the cell of `r`.
+    pub fn assign_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        prev_b: &ACell<F>,
+        prev_c: &ACell<F>,
+    ) -> Result<ACell<F>, Error> {
+        let modulus = self.modulus;
+        let (r, d) = layouter.assign_region(
+            || "next row",
+            |mut region| {
+                self.config.fibo.selector.enable(&mut region, 0)?;
+                self.config.q_mod.enable(&mut region, 0)?;
+
+                let [col_a, col_b, col_c] = self.config.fibo.advice;
+                let a = prev_b.0.copy_advice(|| "a", &mut region, col_a, 0)?;
+                let b = prev_c.0.copy_advice(|| "b", &mut region, col_b, 0)?;
+
+                // a, b < m < 2^64, so their sum fits in a u128
+                let sum = a
+                    .value()
+                    .zip(b.value())
+                    .map(|(a, b)| u128::from(to_u64(a)) + u128::from(to_u64(b)));
+                let quotient = sum.map(|sum| sum >= u128::from(modulus));
+                let r = sum.zip(quotient).map(|(sum, quotient)| {
+                    F::from_u128(if quotient { sum - u128::from(modulus) } else { sum })
+                });
+
+                region.assign_advice(|| "c", col_c, 0, || sum.map(F::from_u128))?;
+                region.assign_advice(
+                    || "q",
+                    self.config.quotient,
+                    0,
+                    || quotient.map(|quotient| F::from(quotient as u64)),
+                )?;
+                let r = region.assign_advice(|| "r", self.config.remainder, 0, || r)?;
+                let d = self.assign_less(&mut region, 0, r.value().copied())?;
+                Ok((r, d))
+            },
+        )?;
+
+        for cell in [&r, &d] {
+            self.range.copy_check(layouter.namespace(|| "below m"), cell, self.num_bits())?;
+        }
+        Ok(ACell(r))
+    }
+
+    // Assigns m and d = m - 1 - r on `row`, next to r.
+    fn assign_less(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        r: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_less.enable(region, row)?;
+        let m = F::from(self.modulus);
+        region.assign_fixed(|| "m", self.config.modulus, row, || Value::known(m))?;
+        region.assign_advice(|| "m - 1 - r", self.config.diff, row, || {
+            r.map(|r| m - F::ONE - r)
+        })
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.fibo.expose_public(layouter, cell, row)
+    }
+}
+
+/// The low 64 bits of `value`.
+fn to_u64<F: PrimeFieldBits>(value: &F) -> u64 {
+    let bits: Vec<bool> = value.to_le_bits().iter().by_vals().take(64).collect();
+    bits.iter().rev().fold(0, |acc, bit| (acc << 1) + *bit as u64)
+}
+
+/// Proves `F(n) mod m` for the public `F(0), F(1) < m`.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F> {
+    n: usize,
+    modulus: u64,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits> FibonacciCircuit<F> {
+    pub fn new(n: usize, modulus: u64) -> Self {
+        assert!(n >= 1, "the table needs at least F(0) and F(1)");
+        assert!(modulus >= 2, "every value is 0 mod 1");
+        Self {
+            n,
+            modulus,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The public inputs `[F(0), F(1), F(n) mod m]`.
+    pub fn instances(&self, f0: u64, f1: u64) -> Vec<Vec<F>> {
+        assert!(f0 < self.modulus && f1 < self.modulus, "F(0) and F(1) must be below m");
+        let out = fibonacci_mod(self.n, f0, f1, self.modulus);
+        vec![vec![F::from(f0), F::from(f1), F::from(out)]]
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let quotient = meta.advice_column();
+        let remainder = meta.advice_column();
+        let diff = meta.advice_column();
+        let value = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, quotient, remainder, diff, value, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config, self.modulus);
+        chip.load_table(&mut layouter)?;
+
+        let (mut prev_b, mut prev_c) = chip.assign_first_rows(layouter.namespace(|| "start"))?;
+        for _ in 2..=self.n {
+            let c = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c;
+        }
+
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, OUT_ROW)
+    }
+}
+
+/// Native reference: `F(n) mod m`, starting from `F(0) = f0`, `F(1) = f1`.
+pub fn fibonacci_mod(n: usize, f0: u64, f1: u64, modulus: u64) -> u64 {
+    let modulus = u128::from(modulus);
+    let (mut a, mut b) = (u128::from(f0) % modulus, u128::from(f1) % modulus);
+    for _ in 0..n {
+        (a, b) = (b, (a + b) % modulus);
+    }
+    a as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example7;
+    use crate::stats::min_k;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prover(circuit: &FibonacciCircuit<Fp>, instances: Vec<Vec<Fp>>) -> MockProver<Fp> {
+        MockProver::run(min_k(circuit).unwrap(), circuit, instances).unwrap()
+    }
+
+    #[test]
+    fn test_example8() {
+        // the widths of m - 1 go through every range-check strategy
+        for modulus in [2, 7, 10, 256, 1_000_000_007, u64::MAX] {
+            for n in [1, 2, 9, 100] {
+                let circuit = FibonacciCircuit::<Fp>::new(n, modulus);
+                let instances = circuit.instances(1, 1);
+                prover(&circuit, instances.clone()).assert_satisfied();
+
+                let mut wrong = instances;
+                wrong[0][OUT_ROW] += Fp::one();
+                assert!(prover(&circuit, wrong).verify().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_as_integer() {
+        // below the modulus, F(n) mod m is the integer F(n) of example7
+        for n in 2..=example7::MAX_N {
+            let integer = example7::fibonacci(n, 1, 1).unwrap();
+            assert_eq!(fibonacci_mod(n, 1, 1, u64::MAX), integer % u64::MAX);
+        }
+        // F[9] = 55 = 6 mod 7
+        assert_eq!(fibonacci_mod(9, 1, 1, 7), 6);
+        // the Pisano period of 10 is 60
+        assert_eq!(fibonacci_mod(60, 0, 1, 10), 0);
+        assert_eq!(fibonacci_mod(61, 0, 1, 10), 1);
+    }
+
+    #[test]
+    fn test_not_reduced() {
+        let circuit = FibonacciCircuit::<Fp>::new(9, 7);
+
+        // 55 = F[9] satisfies a + b = q * m + c for some q, but is not below m
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        assert!(prover(&circuit, instances).verify().is_err());
+
+        // nor can the starting values be
+        let instances = vec![vec![Fp::from(8), Fp::from(1), Fp::from(fibonacci_mod(9, 8, 1, 7))]];
+        assert!(prover(&circuit, instances).verify().is_err());
+    }
+
+    #[test]
+    fn test_to_u64() {
+        assert_eq!(to_u64(&Fp::from(u64::MAX)), u64::MAX);
+        assert_eq!(to_u64(&Fp::from(0b1011)), 0b1011);
+    }
+}
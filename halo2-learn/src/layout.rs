@@ -0,0 +1,283 @@
+//! Renders the layout of any circuit to a PNG or an SVG, picking `k` and the canvas size
+//! from the circuit itself instead of hard-coding them.
+//!
+//! ```ignore
+//! use halo2_examples::layout::{render_to_file, LayoutOptions};
+//!
+//! let options = LayoutOptions {
+//!     title: Some("Fib 1".into()),
+//!     highlight_regions: vec!["next row".into()],
+//!     ..Default::default()
+//! };
+//! render_to_file(&circuit, "fib-1-layout.svg", &options)?;
+//! ```
+
+use std::{error::Error, ops::Range, path::Path};
+
+use ff::Field;
+use halo2_proofs::{
+    dev::CircuitLayout,
+    plonk::{self, Circuit},
+};
+use plotters::{
+    coord::{types::RangedCoordusize, Shift},
+    prelude::*,
+};
+
+use crate::stats::{blinding_rows, fit_k, DryRun};
+pub use crate::witness::Region as RegionInfo;
+
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    pub title: Option<String>,
+    /// `None` picks the smallest `k` the circuit fits in.
+    pub k: Option<u32>,
+    /// Region names, as in `CircuitLayout::show_labels`.
+    pub show_labels: bool,
+    pub mark_equality_cells: bool,
+    pub show_equality_constraints: bool,
+    /// Regions whose name contains one of these strings are outlined, across all columns.
+    pub highlight_regions: Vec<String>,
+    /// Draws all `2^k` rows and shades the unused and blinding rows; otherwise only the
+    /// used rows are drawn.
+    pub mark_unused_rows: bool,
+    /// Size of a cell in pixels.
+    pub column_width: u32,
+    pub row_height: u32,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            k: None,
+            show_labels: true,
+            mark_equality_cells: false,
+            show_equality_constraints: false,
+            highlight_regions: vec![],
+            mark_unused_rows: false,
+            column_width: 48,
+            row_height: 12,
+        }
+    }
+}
+
+/// What a dry run of the circuit's synthesis found out about its layout.
+#[derive(Debug, Clone)]
+pub struct LayoutInfo {
+    pub k: u32,
+    /// Rows `0..rows` have at least one assigned cell or enabled selector.
+    pub rows: usize,
+    /// Rows that can be assigned at this `k`; the others hold blinding factors.
+    pub usable_rows: usize,
+    /// An upper bound of the number of drawn columns (selectors may be combined).
+    pub columns: usize,
+    pub regions: Vec<RegionInfo>,
+}
+
+impl LayoutInfo {
+    /// Runs the floor planner of `circuit` without computing any witness. With `k = None`
+    /// the smallest `k` that fits the used rows and the blinding rows is picked.
+    pub fn new<F: Field, C: Circuit<F>>(circuit: &C, k: Option<u32>) -> Result<Self, plonk::Error> {
+        let (cs, dry_run) = DryRun::run(circuit)?;
+
+        let k = k.unwrap_or_else(|| fit_k(&cs, dry_run.rows));
+
+        Ok(Self {
+            k,
+            rows: dry_run.rows,
+            usable_rows: (1usize << k).saturating_sub(blinding_rows(&cs)),
+            columns: cs.num_instance_columns()
+                + cs.num_advice_columns()
+                + cs.num_fixed_columns()
+                + cs.num_selectors(),
+            regions: dry_run.regions,
+        })
+    }
+
+    fn view_rows(&self, options: &LayoutOptions) -> usize {
+        if options.mark_unused_rows {
+            1 << self.k
+        } else {
+            self.rows.max(1)
+        }
+    }
+
+    /// Canvas size in pixels for these options, title included.
+    pub fn canvas_size(&self, options: &LayoutOptions) -> (u32, u32) {
+        let title = if options.title.is_some() { TITLE_HEIGHT } else { 0 };
+        let width = (self.columns as u32 * options.column_width).max(MIN_WIDTH);
+        let height = self.view_rows(options) as u32 * options.row_height + title;
+        (width, height)
+    }
+}
+
+const TITLE_HEIGHT: u32 = 60;
+const MIN_WIDTH: u32 = 400;
+
+/// Renders the layout of `circuit` on `root`, which should have the size given by
+/// [`LayoutInfo::canvas_size`].
+pub fn render<F, C, DB>(
+    circuit: &C,
+    root: &DrawingArea<DB, Shift>,
+    options: &LayoutOptions,
+) -> Result<LayoutInfo, Box<dyn Error>>
+where
+    F: Field,
+    C: Circuit<F>,
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let info = LayoutInfo::new(circuit, options.k)?;
+    render_with(info, circuit, root, options)
+}
+
+// `render`, with the `LayoutInfo` of `circuit` for these options already computed.
+fn render_with<F, C, DB>(
+    info: LayoutInfo,
+    circuit: &C,
+    root: &DrawingArea<DB, Shift>,
+    options: &LayoutOptions,
+) -> Result<LayoutInfo, Box<dyn Error>>
+where
+    F: Field,
+    C: Circuit<F>,
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let root = match &options.title {
+        Some(title) => root.titled(title, ("sans-serif", 30))?,
+        None => root.clone(),
+    };
+
+    let view_rows = info.view_rows(options);
+    CircuitLayout::default()
+        .view_height(0..view_rows)
+        .show_labels(options.show_labels)
+        .mark_equality_cells(options.mark_equality_cells)
+        .show_equality_constraints(options.show_equality_constraints)
+        .render(info.k, circuit, &root)?;
+
+    // Same coordinates as `CircuitLayout`: (column, row), row 0 at the top. The width of
+    // the overlays does not matter since they span every column.
+    let overlay = root.apply_coord_spec(Cartesian2d::<RangedCoordusize, RangedCoordusize>::new(
+        0..info.columns,
+        0..view_rows,
+        root.get_pixel_range(),
+    ));
+
+    if options.mark_unused_rows {
+        let shade = |rows: Range<usize>, color: RGBColor| {
+            overlay.draw(&Rectangle::new(
+                [(0, rows.start), (info.columns, rows.end)],
+                color.mix(0.4).filled(),
+            ))
+        };
+        shade(info.rows..info.usable_rows, RGBColor(200, 200, 200))?;
+        shade(info.usable_rows..view_rows, RGBColor(90, 90, 90))?;
+    }
+
+    for region in info.regions.iter().filter(|region| {
+        options
+            .highlight_regions
+            .iter()
+            .any(|name| region.name.contains(name.as_str()))
+    }) {
+        let rows = region.rows.start..region.rows.end.min(view_rows);
+        overlay.draw(&Rectangle::new(
+            [(0, rows.start), (info.columns, rows.end)],
+            ShapeStyle::from(&MAGENTA).stroke_width(3),
+        ))?;
+    }
+
+    root.present()?;
+    Ok(info)
+}
+
+/// Renders the layout of `circuit` to `path`, as an SVG if the extension is `.svg` and
+/// as a PNG otherwise.
+pub fn render_to_file<F: Field, C: Circuit<F>>(
+    circuit: &C,
+    path: impl AsRef<Path>,
+    options: &LayoutOptions,
+) -> Result<LayoutInfo, Box<dyn Error>> {
+    let path = path.as_ref();
+    let info = LayoutInfo::new(circuit, options.k)?;
+    let size = info.canvas_size(options);
+
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg")) {
+        render_with(info, circuit, &SVGBackend::new(path, size).into_drawing_area(), options)
+    } else {
+        render_with(info, circuit, &BitMapBackend::new(path, size).into_drawing_area(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{example1, example4::FibonacciCircuit};
+    use crate::range_check::decompose_range_check::DecomposeRangeCheckCircuit;
+    use halo2_proofs::pasta::Fp;
+    use std::marker::PhantomData;
+
+    #[test]
+    fn test_layout_info() {
+        // F(0)..F(1000), then the blinding rows
+        let circuit = FibonacciCircuit::<Fp>::new(1000);
+        let info = LayoutInfo::new(&circuit, None).unwrap();
+        assert_eq!(info.rows, 1001);
+        assert_eq!(info.k, circuit.min_k().unwrap());
+        assert!(info.usable_rows >= info.rows);
+        assert_eq!(info.regions.len(), 1);
+        assert_eq!(info.regions[0].rows, 0..1001);
+
+        // example1: the first row, 7 next rows
+        let info = LayoutInfo::new(&example1::MyCircuit::<Fp>(PhantomData), None).unwrap();
+        assert_eq!(info.rows, 8);
+        assert_eq!(info.k, 4);
+        let info = LayoutInfo::new(&example1::MyCircuit::<Fp>(PhantomData), Some(6)).unwrap();
+        assert_eq!(info.k, 6);
+
+        // the 3-bit table has 15 rows, and is larger than the decomposition
+        let info = LayoutInfo::new(&DecomposeRangeCheckCircuit::<Fp>::new(2, 8), None).unwrap();
+        assert_eq!(info.rows, 15);
+        assert_eq!(info.k, 5);
+    }
+
+    #[test]
+    fn test_canvas_size() {
+        let info = LayoutInfo::new(&FibonacciCircuit::<Fp>::new(100), None).unwrap();
+        let options = LayoutOptions::default();
+        assert_eq!(info.canvas_size(&options).1, 101 * options.row_height);
+
+        let options = LayoutOptions {
+            title: Some("fib".into()),
+            mark_unused_rows: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            info.canvas_size(&options).1,
+            (1 << info.k) * options.row_height + TITLE_HEIGHT
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let dir = std::env::temp_dir();
+        let options = LayoutOptions {
+            title: Some("Decompose".into()),
+            highlight_regions: vec!["Assign value".into()],
+            mark_unused_rows: true,
+            ..Default::default()
+        };
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(2, 8);
+
+        for file in ["halo2-learn-layout-test.svg", "halo2-learn-layout-test.png"] {
+            let path = dir.join(file);
+            let info = render_to_file(&circuit, &path, &options).unwrap();
+            assert_eq!(info.k, 5);
+            assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        }
+    }
+}
@@ -1,3 +1,53 @@
-mod fibonacci;
-mod is_zero;
-mod range_check;
\ No newline at end of file
+pub mod artifact;
+pub mod boolean;
+pub mod comparison;
+pub mod diagnostics;
+pub mod fibonacci;
+pub mod is_zero;
+#[cfg(feature = "dev-graph")]
+pub mod layout;
+pub mod merkle;
+pub mod mutation;
+pub mod poseidon;
+#[cfg(test)]
+mod property;
+pub mod prover;
+pub mod range_check;
+pub mod stats;
+pub mod text_layout;
+pub mod witness;
+
+/// Re-exports of the chips, configs and assigned-value types, so that other circuits can
+/// compose them with a single `use halo2_examples::prelude::*;`.
+pub mod prelude {
+    pub use crate::boolean::{BooleanChip, BooleanConfig};
+    pub use crate::comparison::{Comparison, ComparisonChip, ComparisonConfig};
+    pub use crate::fibonacci::{
+        example1::{FiboChip, FiboConfig},
+        example2::{FiboChip as SingleColumnFiboChip, FiboConfig as SingleColumnFiboConfig},
+        example3::{FunctionChip, FunctionCircuit, FunctionConfig},
+        example4::FibonacciCircuit,
+        example5::{FiboChip as MatrixFiboChip, FiboConfig as MatrixFiboConfig},
+        example6::{FiboChip as DoublingFiboChip, FiboConfig as DoublingFiboConfig},
+        example7::{FiboChip as IntegerFiboChip, FiboConfig as IntegerFiboConfig},
+        example8::{FiboChip as ModFiboChip, FiboConfig as ModFiboConfig},
+        ACell,
+    };
+    pub use crate::is_zero::{IsZeroChip, IsZeroConfig};
+    pub use crate::merkle::{MerkleChip, MerkleCircuit, MerkleConfig, MerkleProof, MerkleTree};
+    pub use crate::poseidon::{
+        chip::{PoseidonChip, PoseidonCircuit, PoseidonConfig},
+        primitives::Spec as PoseidonSpec,
+    };
+    pub use crate::range_check::{
+        chip::{RangeCheckChip, RangeCheckConfig, Strategy as RangeCheckStrategy},
+        decompose_range_check::{
+            DecomposeRangeCheckConfig, RangeConstrained as DecomposeRangeConstrained,
+        },
+        example1::RangeCheckConfig as PolyRangeCheckConfig,
+        example2::RangeCheckConfig as LookupRangeCheckConfig,
+        example3::{
+            RangeCheckConfig as TaggedRangeCheckConfig, RangeConstrained as TaggedRangeConstrained,
+        },
+    };
+}
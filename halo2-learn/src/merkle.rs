@@ -0,0 +1,307 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+
+use crate::boolean::{BooleanChip, BooleanConfig};
+use crate::poseidon::{
+    chip::{PoseidonChip, PoseidonConfig},
+    primitives::{hash, Spec},
+};
+
+// Merkle inclusion proofs with the Poseidon 2-to-1 hash.
+//
+// At each level the current node is hashed with its sibling, in an order given by the
+// index bit of that level (least significant bit first, from the leaf):
+//
+//     left  = bit ? sibling : node
+//     right = bit ? node : sibling
+//     node  = H(left, right)
+//
+// The conditional swap is two `select`s of the `BooleanChip`, which also constrain the
+// index bit to be boolean. The root is the only public input.
+
+/// Width and rate of the 2-to-1 hash.
+pub const T: usize = 3;
+pub const RATE: usize = 2;
+
+pub fn hash_pair<F: PrimeField>(spec: &Spec<F, T>, left: F, right: F) -> F {
+    hash::<F, T, RATE>(spec, &[left, right])
+}
+
+/// A complete binary tree of depth `depth`, built natively to generate the witnesses.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<F: PrimeField> {
+    /// `levels[0]` are the leaves and `levels[depth]` is `[root]`.
+    levels: Vec<Vec<F>>,
+}
+
+/// The witnesses of an inclusion proof.
+#[derive(Debug, Clone)]
+pub struct MerkleProof<F: PrimeField> {
+    pub leaf: F,
+    /// From the leaf's sibling up to the root's children.
+    pub siblings: Vec<F>,
+    /// Bits of the leaf index, least significant first.
+    pub index_bits: Vec<bool>,
+}
+
+impl<F: PrimeField> MerkleTree<F> {
+    /// Missing leaves, up to `2^depth`, are zero.
+    pub fn new(spec: &Spec<F, T>, depth: usize, leaves: &[F]) -> Self {
+        assert!(leaves.len() <= 1 << depth, "too many leaves for depth {}", depth);
+
+        let mut level = leaves.to_vec();
+        level.resize(1 << depth, F::ZERO);
+
+        let mut levels = vec![level];
+        for _ in 0..depth {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_pair(spec, pair[0], pair[1]))
+                .collect();
+            levels.push(next);
+        }
+
+        Self { levels }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.levels[self.depth()][0]
+    }
+
+    pub fn proof(&self, index: usize) -> MerkleProof<F> {
+        assert!(index < 1 << self.depth(), "no leaf {}", index);
+
+        let siblings = (0..self.depth())
+            .map(|level| self.levels[level][(index >> level) ^ 1])
+            .collect();
+        let index_bits = (0..self.depth()).map(|level| (index >> level) & 1 == 1).collect();
+
+        MerkleProof {
+            leaf: self.levels[0][index],
+            siblings,
+            index_bits,
+        }
+    }
+}
+
+impl<F: PrimeField> MerkleProof<F> {
+    /// Native reference of the root computed by the circuit.
+    pub fn root(&self, spec: &Spec<F, T>) -> F {
+        self.siblings
+            .iter()
+            .zip(self.index_bits.iter())
+            .fold(self.leaf, |node, (sibling, bit)| {
+                if *bit {
+                    hash_pair(spec, *sibling, node)
+                } else {
+                    hash_pair(spec, node, *sibling)
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleConfig<F: PrimeField> {
+    pub boolean: BooleanConfig<F>,
+    pub poseidon: PoseidonConfig<F, T, RATE>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleChip<F: PrimeField> {
+    config: MerkleConfig<F>,
+}
+
+impl<F: PrimeField> MerkleChip<F> {
+    pub fn construct(config: MerkleConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MerkleConfig<F> {
+        let boolean = BooleanChip::configure(meta);
+        let poseidon = PoseidonChip::configure(meta, Spec::default());
+        MerkleConfig { boolean, poseidon }
+    }
+
+    /// The root of the tree containing `leaf` at the position given by `index_bits`.
+    pub fn root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[AssignedCell<F, F>],
+        index_bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(siblings.len(), index_bits.len());
+        let boolean = BooleanChip::construct(self.config.boolean.clone());
+        let poseidon = PoseidonChip::<F, T, RATE>::construct(self.config.poseidon.clone());
+
+        let mut node = leaf.clone();
+        for (level, (sibling, bit)) in siblings.iter().zip(index_bits.iter()).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("level {}", level));
+            let left = boolean.select(layouter.namespace(|| "left"), bit, sibling, &node)?;
+            let right = boolean.select(layouter.namespace(|| "right"), bit, &node, sibling)?;
+            node = poseidon.hash(layouter.namespace(|| "hash"), &[left, right])?;
+        }
+
+        Ok(node)
+    }
+}
+
+/// Proves that a private leaf is in the tree whose root is the public input.
+#[derive(Debug, Clone)]
+pub struct MerkleCircuit<F: PrimeField> {
+    pub leaf: Value<F>,
+    pub siblings: Vec<Value<F>>,
+    pub index_bits: Vec<Value<bool>>,
+}
+
+impl<F: PrimeField> MerkleCircuit<F> {
+    pub fn new(proof: &MerkleProof<F>) -> Self {
+        Self {
+            leaf: Value::known(proof.leaf),
+            siblings: proof.siblings.iter().copied().map(Value::known).collect(),
+            index_bits: proof.index_bits.iter().copied().map(Value::known).collect(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleCircuitConfig<F: PrimeField> {
+    pub merkle: MerkleConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+impl<F: PrimeField> Circuit<F> for MerkleCircuit<F> {
+    type Config = MerkleCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            leaf: Value::unknown(),
+            siblings: vec![Value::unknown(); self.depth()],
+            index_bits: vec![Value::unknown(); self.depth()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let merkle = MerkleChip::configure(meta);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        MerkleCircuitConfig { merkle, instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let boolean = BooleanChip::construct(config.merkle.boolean.clone());
+        let chip = MerkleChip::construct(config.merkle);
+
+        let leaf = boolean.assign(layouter.namespace(|| "leaf"), self.leaf)?;
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|sibling| boolean.assign(layouter.namespace(|| "sibling"), *sibling))
+            .collect::<Result<Vec<_>, _>>()?;
+        let index_bits = self
+            .index_bits
+            .iter()
+            .map(|bit| boolean.assign_bool(layouter.namespace(|| "index bit"), *bit))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let root = chip.root(layouter.namespace(|| "root"), &leaf, &siblings, &index_bits)?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::min_k;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn build_tree(depth: usize, num_leaves: u64) -> MerkleTree<Fp> {
+        let leaves: Vec<Fp> = (0..num_leaves).map(|i| Fp::from(100 + i)).collect();
+        MerkleTree::new(&Spec::default(), depth, &leaves)
+    }
+
+    fn mock(circuit: &MerkleCircuit<Fp>, root: Fp) -> MockProver<Fp> {
+        MockProver::run(min_k(circuit).unwrap(), circuit, vec![vec![root]]).unwrap()
+    }
+
+    #[test]
+    fn test_native_tree() {
+        let spec = Spec::default();
+        let tree = build_tree(3, 6);
+        assert_eq!(tree.depth(), 3);
+
+        for index in 0..8 {
+            let proof = tree.proof(index);
+            assert_eq!(proof.root(&spec), tree.root());
+        }
+        assert_eq!(tree.proof(6).leaf, Fp::zero());
+        assert_eq!(tree.proof(5).index_bits, vec![true, false, true]);
+
+        let left = hash_pair(&spec, Fp::from(100), Fp::from(101));
+        assert_eq!(tree.proof(2).siblings[1], left);
+    }
+
+    #[test]
+    fn test_membership() {
+        let tree = build_tree(3, 8);
+        for index in 0..8 {
+            let circuit = MerkleCircuit::new(&tree.proof(index));
+            let prover = mock(&circuit, tree.root());
+            prover.assert_satisfied();
+        }
+
+        let tree = build_tree(5, 20);
+        let circuit = MerkleCircuit::new(&tree.proof(19));
+        let prover = mock(&circuit, tree.root());
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_wrong_sibling() {
+        let tree = build_tree(3, 8);
+        for level in 0..3 {
+            let mut proof = tree.proof(3);
+            proof.siblings[level] += Fp::one();
+            let circuit = MerkleCircuit::new(&proof);
+            let prover = mock(&circuit, tree.root());
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_wrong_index_bits() {
+        let tree = build_tree(3, 8);
+        for level in 0..3 {
+            let mut proof = tree.proof(3);
+            proof.index_bits[level] = !proof.index_bits[level];
+            let circuit = MerkleCircuit::new(&proof);
+            let prover = mock(&circuit, tree.root());
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_wrong_leaf() {
+        let tree = build_tree(3, 8);
+        let mut proof = tree.proof(0);
+        proof.leaf = Fp::from(42);
+        let circuit = MerkleCircuit::new(&proof);
+        let prover = mock(&circuit, tree.root());
+        assert!(prover.verify().is_err());
+    }
+}
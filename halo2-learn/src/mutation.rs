@@ -0,0 +1,382 @@
+//! Looks for under-constrained cells: each advice cell of a valid witness is changed in
+//! turn, and every change `MockProver` still accepts is reported.
+//!
+//! ```text
+//! $ cargo run --release --bin halo2-learn -- mutate fib3 --a 10 --b 10 --c 15
+//! a4[0] "value inv" in "f(a, b, c) = if a == b {c} else {a - b}": 0 -> 1
+//! ```
+//!
+//! Here `a - b = 0`, so the `is_zero` gate holds for any `value_inv`. A survivor is not
+//! always a bug (a witness nobody reads, like `c` when `a != b`, is free too), but every
+//! cell a circuit relies on should be pinned by a gate, a lookup or a copy constraint.
+//!
+//! Only single cells are changed, with a few values around the original one, so a cell
+//! that must change together with another one is not reported.
+
+use std::{any::Any as AnyValue, cell::RefCell, fmt, marker::PhantomData};
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    dev::{MockProver, VerifyFailure},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, FloorPlanner,
+        Fixed, Instance, Selector,
+    },
+};
+
+use crate::{
+    stats::min_k,
+    text_layout::format_value,
+    witness::{CellInfo, WitnessTable},
+};
+
+/// A change of one advice cell that `MockProver` accepted.
+#[derive(Debug, Clone)]
+pub struct Survivor<F> {
+    /// The cell as named in the text tables, e.g. `a4[0]`.
+    pub cell: String,
+    pub column: Column<Any>,
+    pub row: usize,
+    pub info: CellInfo,
+    pub value: F,
+    pub mutated: F,
+}
+
+impl<F: PrimeField> fmt::Display for Survivor<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cell)?;
+        if let Some(annotation) = &self.info.annotation {
+            write!(f, " \"{}\"", annotation)?;
+        }
+        if let Some(region) = &self.info.region {
+            write!(f, " in \"{}\"", region)?;
+        }
+        write!(f, ": {} -> {}", format_value(&self.value), format_value(&self.mutated))
+    }
+}
+
+#[derive(Debug)]
+pub enum MutationError {
+    Synthesis(Error),
+    /// The witness to mutate is not accepted in the first place.
+    Unsatisfied(Vec<VerifyFailure>),
+}
+
+impl From<Error> for MutationError {
+    fn from(e: Error) -> Self {
+        MutationError::Synthesis(e)
+    }
+}
+
+impl fmt::Display for MutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutationError::Synthesis(e) => write!(f, "synthesis failed: {}", e),
+            MutationError::Unsatisfied(failures) => {
+                write!(f, "the witness is not satisfied ({} failures)", failures.len())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MutationError {}
+
+/// The values tried in place of `value`.
+pub fn mutations<F: PrimeField>(value: F) -> Vec<F> {
+    let mut values = vec![];
+    for mutated in [value + F::ONE, value - F::ONE, F::ZERO, F::ONE] {
+        if mutated != value && !values.contains(&mutated) {
+            values.push(mutated);
+        }
+    }
+    values
+}
+
+/// Checks `circuit` with `instances`, then changes each known advice cell to each of its
+/// [`mutations`] until one is accepted. Returns the accepted changes, one per cell.
+pub fn find_unconstrained<F, C>(
+    circuit: &C,
+    instances: &[Vec<F>],
+) -> Result<Vec<Survivor<F>>, MutationError>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F>,
+{
+    let table = WitnessTable::new(circuit, instances)?;
+    let k = min_k(circuit)?;
+
+    let prover = MockProver::run(k, circuit, instances.to_vec())?;
+    prover.verify().map_err(MutationError::Unsatisfied)?;
+
+    let accepts = |column: Column<Any>, row: usize, value: F| -> Result<bool, Error> {
+        TARGET.with(|target| {
+            *target.borrow_mut() = Some(Target {
+                column,
+                row,
+                value: Box::new(value),
+            })
+        });
+        let prover = MockProver::run(k, &Mutant { circuit }, instances.to_vec())?;
+        Ok(prover.verify().is_ok())
+    };
+
+    let mut survivors = vec![];
+    for column in table.columns() {
+        if *column.column_type() != Any::Advice {
+            continue;
+        }
+        for row in 0..table.num_rows() {
+            let Some(Some(value)) = table.value(column, row) else {
+                continue;
+            };
+            for mutated in mutations(value) {
+                if accepts(column, row, mutated)? {
+                    survivors.push(Survivor {
+                        cell: format!("{}[{}]", table.column_name(&column), row),
+                        column,
+                        row,
+                        info: table.info(column, row).cloned().unwrap_or_default(),
+                        value,
+                        mutated,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(survivors)
+}
+
+// The cell to change in the next synthesis. A `FloorPlanner` only sees the circuit as a
+// generic `C`, so the target can't be read from `Mutant` itself.
+struct Target {
+    column: Column<Any>,
+    row: usize,
+    value: Box<dyn AnyValue>,
+}
+
+thread_local! {
+    static TARGET: RefCell<Option<Target>> = RefCell::new(None);
+}
+
+// `circuit`, synthesized by `Mutating` on top of its own floor planner.
+struct Mutant<'a, C> {
+    circuit: &'a C,
+}
+
+impl<'a, F: PrimeField, C: Circuit<F>> Circuit<F> for Mutant<'a, C> {
+    type Config = C::Config;
+    type FloorPlanner = Mutating<C::FloorPlanner>;
+
+    // Only run by `MockProver`, which never asks for it.
+    fn without_witnesses(&self) -> Self {
+        Self {
+            circuit: self.circuit,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+struct Mutating<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for Mutating<P> {
+    fn synthesize<F: ff::Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let target = TARGET.with(|target| target.borrow_mut().take()).map(|target| {
+            let value = *target.value.downcast::<F>().expect("mutation of another field");
+            (target.column, target.row, value)
+        });
+        P::synthesize(&mut MutatingAssignment { cs, target }, circuit, config, constants)
+    }
+}
+
+// Passes everything through to `cs`, except the assignment of the target cell.
+struct MutatingAssignment<'a, F, CS> {
+    cs: &'a mut CS,
+    target: Option<(Column<Any>, usize, F)>,
+}
+
+impl<'a, F: ff::Field, CS: Assignment<F>> Assignment<F> for MutatingAssignment<'a, F, CS> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.enter_region(name)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match self.target {
+            Some((target, target_row, value)) if target == column.into() && target_row == row => {
+                self.cs
+                    .assign_advice(annotation, column, row, || Value::known(value))
+            }
+            _ => self.cs.assign_advice(annotation, column, row, to),
+        }
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{example1, example3::FunctionCircuit};
+    use crate::range_check::example3;
+    use ff::Field;
+    use halo2_proofs::pasta::Fp;
+
+    #[test]
+    fn test_free_value_inv() {
+        // a == b: the is_zero gate is `(a - b) * (1 - (a - b) * value_inv)`, 0 for any inverse
+        let circuit = FunctionCircuit {
+            a: Fp::from(10),
+            b: Fp::from(10),
+            c: Fp::from(15),
+        };
+        let survivors = find_unconstrained(&circuit, &[]).unwrap();
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].info.annotation.as_deref(), Some("value inv"));
+        assert_eq!(survivors[0].value, Fp::ZERO);
+        assert_eq!(
+            survivors[0].to_string(),
+            "a4[0] \"value inv\" in \"f(a, b, c) = if a == b {c} else {a - b}\": 0 -> 1"
+        );
+    }
+
+    #[test]
+    fn test_unused_input() {
+        // a != b: the output is a - b and `c` is never read
+        let circuit = FunctionCircuit {
+            a: Fp::from(10),
+            b: Fp::from(12),
+            c: Fp::from(15),
+        };
+        let survivors = find_unconstrained(&circuit, &[]).unwrap();
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].cell, "a2[0]");
+        assert_eq!(survivors[0].mutated, Fp::from(16));
+    }
+
+    #[test]
+    fn test_fibonacci_constrained() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let circuit = example1::MyCircuit(std::marker::PhantomData);
+        assert!(find_unconstrained(&circuit, &instances).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tagged_range_check() {
+        // the table holds one `num_bits` tag per value, so the tag is pinned by the lookup,
+        // while the value is only pinned to the 4-bit values
+        let circuit = example3::MyCircuit::<Fp, 8, 256> {
+            num_bits: Value::known(4),
+            value: Value::known(Fp::from(9).into()),
+        };
+        let survivors = find_unconstrained(&circuit, &[]).unwrap();
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].info.annotation.as_deref(), Some("value"));
+        assert_eq!(survivors[0].mutated, Fp::from(10));
+    }
+
+    #[test]
+    fn test_invalid_witness() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(56)]];
+        let circuit = example1::MyCircuit(std::marker::PhantomData);
+        assert!(matches!(
+            find_unconstrained(&circuit, &instances),
+            Err(MutationError::Unsatisfied(_))
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+//! The Poseidon hash: a native implementation in `primitives`, and the chip that
+//! constrains the same permutation and sponge in `chip`.
+
+pub mod chip;
+pub mod primitives;
@@ -0,0 +1,427 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+
+use super::primitives::{domain_tag, Spec};
+
+// One row per round: the gate of round r reads the state at row r, the round constants
+// in the fixed columns at row r, and constrains the state at row r + 1.
+//
+//   state_0 .. state_{T-1} | message_0 .. message_{RATE-1} | rc_0 .. rc_{T-1} | q_absorb | q_full | q_partial
+//  ------------------------+-------------------------------+------------------+----------+--------+-----------
+//   0 ... 0   tag          |   m_0 ... m_{RATE-1}          |                  |    1     |   0    |    0
+//   s_0 (state + message)  |                               |   c_0            |    0     |   1    |    0
+//   s_1                    |                               |   c_1            |    0     |   1    |    0
+//   ...                    |                               |   ...            |          |        |
+//   s_{R_F/2}              |                               |   c_{R_F/2}      |    0     |   0    |    1
+//   ...                    |                               |   ...            |          |        |
+//   s_R = output           |   next chunk, if any          |                  |    1     |   0    |    0
+//
+// The S-box of a full round is applied to every element, so the gates have degree 5
+// (6 with the selector) without any intermediate column.
+
+#[derive(Debug, Clone)]
+pub struct PoseidonConfig<F: PrimeField, const T: usize, const RATE: usize> {
+    pub state: [Column<Advice>; T],
+    pub message: [Column<Advice>; RATE],
+    pub rc: [Column<Fixed>; T],
+    pub constant: Column<Fixed>,
+    pub q_absorb: Selector,
+    pub q_full: Selector,
+    pub q_partial: Selector,
+    pub spec: Spec<F, T>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonChip<F: PrimeField, const T: usize, const RATE: usize> {
+    config: PoseidonConfig<F, T, RATE>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
+    pub fn construct(config: PoseidonConfig<F, T, RATE>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, spec: Spec<F, T>) -> PoseidonConfig<F, T, RATE> {
+        assert!(RATE > 0 && RATE < T, "the sponge needs a capacity");
+
+        let state = [(); T].map(|_| meta.advice_column());
+        let message = [(); RATE].map(|_| meta.advice_column());
+        let rc = [(); T].map(|_| meta.fixed_column());
+        let constant = meta.fixed_column();
+        let q_absorb = meta.selector();
+        let q_full = meta.selector();
+        let q_partial = meta.selector();
+
+        for column in state.iter().chain(message.iter()) {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+
+        let pow_5 = |x: Expression<F>| {
+            let x2 = x.clone() * x.clone();
+            x2.clone() * x2 * x
+        };
+
+        // next = MDS * sbox(cur + rc), with the S-box on every element or only the first.
+        for (name, selector, full) in [("full round", q_full, true), ("partial round", q_partial, false)] {
+            let mds = spec.mds;
+            meta.create_gate(name, |meta| {
+                let q = meta.query_selector(selector);
+                let sboxed: Vec<Expression<F>> = (0..T)
+                    .map(|j| {
+                        let x = meta.query_advice(state[j], Rotation::cur())
+                            + meta.query_fixed(rc[j], Rotation::cur());
+                        if full || j == 0 {
+                            pow_5(x)
+                        } else {
+                            x
+                        }
+                    })
+                    .collect();
+
+                (0..T)
+                    .map(|i| {
+                        let next = meta.query_advice(state[i], Rotation::next());
+                        let mixed = sboxed
+                            .iter()
+                            .zip(mds[i].iter())
+                            .fold(Expression::Constant(F::ZERO), |acc, (s, m)| {
+                                acc + s.clone() * Expression::Constant(*m)
+                            });
+                        q.clone() * (next - mixed)
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        // The message is added to the rate elements; the capacity is left untouched.
+        meta.create_gate("absorb", |meta| {
+            let q = meta.query_selector(q_absorb);
+            (0..T)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let input = if i < RATE {
+                        meta.query_advice(message[i], Rotation::cur())
+                    } else {
+                        Expression::Constant(F::ZERO)
+                    };
+                    q.clone() * (next - cur - input)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        PoseidonConfig {
+            state,
+            message,
+            rc,
+            constant,
+            q_absorb,
+            q_full,
+            q_partial,
+            spec,
+        }
+    }
+
+    /// Witnesses the elements of a message, ready to be hashed.
+    pub fn load_message(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Value<F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "load message",
+            |mut region| {
+                message
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        region.assign_advice(|| format!("m_{}", i), self.config.message[0], i, || *value)
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// The Poseidon permutation of `state`.
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>; T],
+    ) -> Result<[AssignedCell<F, F>; T], Error> {
+        layouter.assign_region(
+            || "poseidon permutation",
+            |mut region| {
+                let state = state
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        cell.copy_advice(|| format!("state_{}", i), &mut region, self.config.state[i], 0)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let state = self.assign_rounds(&mut region, 0, state)?;
+                Ok(state.try_into().unwrap())
+            },
+        )
+    }
+
+    /// Hashes a message of known length, like [`super::primitives::hash`].
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!message.is_empty(), "nothing to hash");
+
+        layouter.assign_region(
+            || "poseidon hash",
+            |mut region| {
+                let mut state = (0..T)
+                    .map(|i| {
+                        let value = if i == RATE {
+                            domain_tag(message.len())
+                        } else {
+                            F::ZERO
+                        };
+                        region.assign_advice_from_constant(
+                            || format!("initial state_{}", i),
+                            self.config.state[i],
+                            0,
+                            value,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let mut offset = 0;
+                for chunk in message.chunks(RATE) {
+                    self.config.q_absorb.enable(&mut region, offset)?;
+                    let inputs = (0..RATE)
+                        .map(|i| match chunk.get(i) {
+                            Some(cell) => cell.copy_advice(
+                                || format!("m_{}", i),
+                                &mut region,
+                                self.config.message[i],
+                                offset,
+                            ),
+                            None => region.assign_advice_from_constant(
+                                || "padding",
+                                self.config.message[i],
+                                offset,
+                                F::ZERO,
+                            ),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    state = (0..T)
+                        .map(|i| {
+                            let mut value = state[i].value().copied();
+                            if i < RATE {
+                                value = value + inputs[i].value();
+                            }
+                            region.assign_advice(
+                                || format!("absorbed state_{}", i),
+                                self.config.state[i],
+                                offset + 1,
+                                || value,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    offset += 1;
+
+                    state = self.assign_rounds(&mut region, offset, state)?;
+                    offset += self.config.spec.rounds();
+                }
+
+                Ok(state[0].clone())
+            },
+        )
+    }
+
+    // Assigns the rounds below `state`, which is at row `offset`, and returns the output.
+    fn assign_rounds(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: Vec<AssignedCell<F, F>>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let spec = &self.config.spec;
+        let mut values: Value<Vec<F>> = state.iter().map(|cell| cell.value().copied()).collect();
+        let mut state = state;
+
+        for round in 0..spec.rounds() {
+            let row = offset + round;
+            if spec.is_full_round(round) {
+                self.config.q_full.enable(region, row)?;
+            } else {
+                self.config.q_partial.enable(region, row)?;
+            }
+            for (j, c) in spec.round_constants[round].iter().enumerate() {
+                region.assign_fixed(
+                    || format!("round {} rc_{}", round, j),
+                    self.config.rc[j],
+                    row,
+                    || Value::known(*c),
+                )?;
+            }
+
+            values = values.map(|values| {
+                let mut next: [F; T] = values.try_into().unwrap();
+                spec.round(round, &mut next);
+                next.to_vec()
+            });
+            state = (0..T)
+                .map(|i| {
+                    region.assign_advice(
+                        || format!("round {} state_{}", round + 1, i),
+                        self.config.state[i],
+                        row + 1,
+                        || values.as_ref().map(|values| values[i]),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        Ok(state)
+    }
+}
+
+/// Proves knowledge of a message whose hash is the public input.
+#[derive(Debug, Clone)]
+pub struct PoseidonCircuit<F: PrimeField, const T: usize, const RATE: usize> {
+    pub message: Vec<Value<F>>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> PoseidonCircuit<F, T, RATE> {
+    pub fn new(message: &[F]) -> Self {
+        Self {
+            message: message.iter().copied().map(Value::known).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonCircuitConfig<F: PrimeField, const T: usize, const RATE: usize> {
+    pub poseidon: PoseidonConfig<F, T, RATE>,
+    pub instance: Column<Instance>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> Circuit<F> for PoseidonCircuit<F, T, RATE> {
+    type Config = PoseidonCircuitConfig<F, T, RATE>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![Value::unknown(); self.message.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let poseidon = PoseidonChip::configure(meta, Spec::default());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        PoseidonCircuitConfig { poseidon, instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = PoseidonChip::construct(config.poseidon);
+
+        let message = chip.load_message(layouter.namespace(|| "message"), &self.message)?;
+        let digest = chip.hash(layouter.namespace(|| "hash"), &message)?;
+        layouter.constrain_instance(digest.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::primitives::hash;
+    use crate::stats::min_k;
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{Fp, Fq},
+    };
+
+    fn check<F: PrimeField, const T: usize, const RATE: usize>(len: u64) {
+        let message: Vec<F> = (0..len).map(|i| F::from(i * 7 + 1)).collect();
+        let digest = hash::<F, T, RATE>(&Spec::default(), &message);
+        let circuit = PoseidonCircuit::<F, T, RATE>::new(&message);
+        let k = min_k(&circuit).unwrap();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![digest]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![digest + F::ONE]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_hash_fp() {
+        // one chunk, and three chunks with padding
+        check::<Fp, 3, 2>(2);
+        check::<Fp, 3, 2>(5);
+    }
+
+    #[test]
+    fn test_hash_fq() {
+        check::<Fq, 3, 2>(1);
+        check::<Fq, 3, 2>(4);
+    }
+
+    #[test]
+    fn test_hash_wider() {
+        check::<Fp, 5, 4>(5);
+        check::<Fq, 4, 2>(3);
+    }
+
+    #[derive(Debug, Clone)]
+    struct PermutationCircuit {
+        state: [Value<Fp>; 3],
+    }
+
+    impl Circuit<Fp> for PermutationCircuit {
+        type Config = PoseidonCircuitConfig<Fp, 3, 2>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                state: [Value::unknown(); 3],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            PoseidonCircuit::<Fp, 3, 2>::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = PoseidonChip::construct(config.poseidon);
+            let state = chip.load_message(layouter.namespace(|| "state"), &self.state)?;
+            let state = chip.permute(layouter.namespace(|| "permute"), &state.try_into().unwrap())?;
+            for (row, cell) in state.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_permutation() {
+        let input = [Fp::from(0), Fp::from(1), Fp::from(2)];
+        let mut output = input;
+        Spec::<Fp, 3>::default().permute(&mut output);
+
+        let circuit = PermutationCircuit {
+            state: input.map(Value::known),
+        };
+        let k = min_k(&circuit).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![output.to_vec()]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![input.to_vec()]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use ff::{Field, PrimeField};
+
+// Native Poseidon, the reference for the chip.
+//
+// A permutation of T field elements is R_F / 2 full rounds, R_P partial rounds, then
+// R_F / 2 full rounds again. Each round:
+//  - adds the round constants to the state,
+//  - applies the S-box x^5 to every element (full round) or to the first one only
+//    (partial round),
+//  - multiplies the state by the MDS matrix.
+//
+// The round constants come from the Grain LFSR described in the Poseidon paper, seeded
+// with the field size, T, R_F and R_P, so they are generated here instead of being
+// copied from somewhere. The MDS matrix is the Cauchy matrix 1 / (x_i + y_j) with
+// x_i = i and y_j = T + j.
+
+/// The S-box exponent; x -> x^5 is a permutation of the pasta fields since
+/// gcd(5, p - 1) = 1.
+pub const ALPHA: u64 = 5;
+
+pub fn sbox<F: Field>(x: F) -> F {
+    x.square().square() * x
+}
+
+#[derive(Debug, Clone)]
+pub struct Spec<F: PrimeField, const T: usize> {
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    /// One `[F; T]` per round.
+    pub round_constants: Vec<[F; T]>,
+    pub mds: [[F; T]; T],
+}
+
+impl<F: PrimeField, const T: usize> Default for Spec<F, T> {
+    /// The number of rounds of the `t = 3` instance used by Zcash (`P128Pow5T3`).
+    /// Other widths should pick theirs with [`Spec::new`].
+    fn default() -> Self {
+        Self::new(8, 56)
+    }
+}
+
+impl<F: PrimeField, const T: usize> Spec<F, T> {
+    pub fn new(full_rounds: usize, partial_rounds: usize) -> Self {
+        assert!(T >= 2, "the state needs at least a rate and a capacity element");
+        assert!(full_rounds % 2 == 0, "full rounds are split before and after the partial ones");
+
+        let mut grain = Grain::new(F::NUM_BITS, T, full_rounds, partial_rounds);
+        let round_constants = (0..full_rounds + partial_rounds)
+            .map(|_| [(); T].map(|_| grain.next_field_element::<F>()))
+            .collect();
+
+        let mds = core::array::from_fn(|i| {
+            core::array::from_fn(|j| {
+                F::from((i + T + j) as u64)
+                    .invert()
+                    .expect("x_i + y_j is never zero")
+            })
+        });
+
+        Self {
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    pub fn is_full_round(&self, round: usize) -> bool {
+        let half = self.full_rounds / 2;
+        round < half || round >= half + self.partial_rounds
+    }
+
+    /// Applies round number `round` to `state`.
+    pub fn round(&self, round: usize, state: &mut [F; T]) {
+        for (s, c) in state.iter_mut().zip(self.round_constants[round].iter()) {
+            *s += c;
+        }
+
+        if self.is_full_round(round) {
+            for s in state.iter_mut() {
+                *s = sbox(*s);
+            }
+        } else {
+            state[0] = sbox(state[0]);
+        }
+
+        *state = core::array::from_fn(|i| {
+            self.mds[i]
+                .iter()
+                .zip(state.iter())
+                .fold(F::ZERO, |acc, (m, s)| acc + *m * s)
+        });
+    }
+
+    pub fn permute(&self, state: &mut [F; T]) {
+        for round in 0..self.rounds() {
+            self.round(round, state);
+        }
+    }
+}
+
+/// The initial capacity element of a sponge hashing `len` elements, so that messages of
+/// different lengths are in different domains.
+pub fn domain_tag<F: PrimeField>(len: usize) -> F {
+    F::from_u128((len as u128) << 64)
+}
+
+/// Hashes a message of known length with a sponge of width T and rate `RATE`.
+///
+/// The state is `[rate elements | capacity elements]`, the first capacity element is the
+/// domain tag and the message is padded with zeros to a multiple of `RATE`. Each chunk
+/// is added to the rate elements before a permutation; the output is the first element.
+pub fn hash<F: PrimeField, const T: usize, const RATE: usize>(spec: &Spec<F, T>, message: &[F]) -> F {
+    assert!(RATE > 0 && RATE < T, "the sponge needs a capacity");
+    assert!(!message.is_empty(), "nothing to hash");
+
+    let mut state = [F::ZERO; T];
+    state[RATE] = domain_tag(message.len());
+
+    for chunk in message.chunks(RATE) {
+        for (s, m) in state.iter_mut().zip(chunk.iter()) {
+            *s += m;
+        }
+        spec.permute(&mut state);
+    }
+
+    state[0]
+}
+
+/// The Grain LFSR of the Poseidon paper (appendix F), used to generate round constants.
+struct Grain {
+    state: [bool; 80],
+}
+
+impl Grain {
+    fn new(field_bits: u32, t: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut bits = Vec::with_capacity(80);
+        let mut push = |value: u64, width: usize| {
+            for i in (0..width).rev() {
+                bits.push((value >> i) & 1 == 1);
+            }
+        };
+        // field = 1 (prime field), sbox = 0 (x^alpha)
+        push(1, 2);
+        push(0, 4);
+        push(field_bits as u64, 12);
+        push(t as u64, 12);
+        push(full_rounds as u64, 10);
+        push(partial_rounds as u64, 10);
+        push((1 << 30) - 1, 30);
+
+        let mut grain = Self {
+            state: bits.try_into().unwrap(),
+        };
+        for _ in 0..160 {
+            grain.next_bit();
+        }
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.rotate_left(1);
+        self.state[79] = bit;
+        bit
+    }
+
+    // Bits are produced in pairs: if the first one is 1 the second one is output,
+    // otherwise it is dropped.
+    fn next_output_bit(&mut self) -> bool {
+        loop {
+            let keep = self.next_bit();
+            let bit = self.next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// `F::NUM_BITS` bits, most significant first, rejected until the value is below
+    /// the modulus. Assumes a little-endian `Repr`, like the pasta fields.
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let mut repr = F::Repr::default();
+            let bytes = repr.as_mut();
+            for i in (0..F::NUM_BITS as usize).rev() {
+                if self.next_output_bit() {
+                    bytes[i / 8] |= 1 << (i % 8);
+                }
+            }
+            if let Some(value) = Option::from(F::from_repr(repr)) {
+                return value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::pasta::{Fp, Fq};
+
+    #[test]
+    fn test_spec() {
+        let spec = Spec::<Fp, 3>::default();
+        assert_eq!(spec.rounds(), 64);
+        assert_eq!(spec.round_constants.len(), 64);
+        assert!((0..4).all(|round| spec.is_full_round(round)));
+        assert!((4..60).all(|round| !spec.is_full_round(round)));
+        assert!((60..64).all(|round| spec.is_full_round(round)));
+
+        // the constants are deterministic, and depend on the parameters
+        let again = Spec::<Fp, 3>::default();
+        assert_eq!(spec.round_constants, again.round_constants);
+        let other = Spec::<Fp, 3>::new(8, 57);
+        assert_ne!(spec.round_constants[0], other.round_constants[0]);
+        let wider = Spec::<Fp, 5>::default();
+        assert_ne!(spec.round_constants[0][0], wider.round_constants[0][0]);
+    }
+
+    #[test]
+    fn test_mds() {
+        let spec = Spec::<Fq, 3>::default();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(spec.mds[i][j] * Fq::from((3 + i + j) as u64), Fq::one());
+            }
+        }
+    }
+
+    #[test]
+    fn test_sbox() {
+        assert_eq!(sbox(Fp::from(3)), Fp::from(243));
+        assert_eq!(sbox(Fp::from(3)), Fp::from(3).pow_vartime([ALPHA]));
+    }
+
+    #[test]
+    fn test_hash() {
+        let spec = Spec::<Fp, 3>::default();
+        let message = [Fp::from(1), Fp::from(2)];
+
+        // a permutation of the padded message, by hand
+        let mut state = [Fp::from(1), Fp::from(2), domain_tag(2)];
+        spec.permute(&mut state);
+        assert_eq!(hash::<_, 3, 2>(&spec, &message), state[0]);
+
+        // zero padding does not collide thanks to the domain tag
+        assert_ne!(
+            hash::<_, 3, 2>(&spec, &[Fp::from(1)]),
+            hash::<_, 3, 2>(&spec, &[Fp::from(1), Fp::zero()])
+        );
+        assert_ne!(hash::<_, 3, 2>(&spec, &message), hash::<_, 3, 2>(&spec, &[Fp::from(2), Fp::from(1)]));
+    }
+}
@@ -0,0 +1,203 @@
+//! Property tests for the example circuits: proptest generates the witnesses, and each
+//! circuit must be satisfied exactly when a native reference predicate holds. A failing
+//! case is shrunk by proptest to a minimal witness before it is reported.
+//!
+//! ```text
+//! $ cargo test property
+//! ```
+
+use ff::{FromUniformBytes, PrimeField};
+use halo2_proofs::{dev::MockProver, plonk::Circuit};
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+use crate::stats::min_k;
+
+/// Field elements: small values, values just below the modulus (`-1`, `-2`, ...) and
+/// uniformly random ones. Each kind shrinks towards its smallest value, 0 for the random
+/// ones.
+pub fn field<F: FromUniformBytes<64>>() -> impl Strategy<Value = F> {
+    prop_oneof![
+        (0u64..256).prop_map(F::from),
+        (1u64..256).prop_map(|v| -F::from(v)),
+        any::<u64>().prop_map(F::from),
+        (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(low, high)| {
+            let mut bytes = [0; 64];
+            bytes[..32].copy_from_slice(&low);
+            bytes[32..].copy_from_slice(&high);
+            F::from_uniform_bytes(&bytes)
+        }),
+    ]
+}
+
+/// Integers around a `bound`: below it, up to `2 * bound`, or anywhere in `u64`, so that
+/// both sides of a range check are hit.
+pub fn around(bound: u64) -> impl Strategy<Value = u64> {
+    prop_oneof![0..bound, bound..bound.saturating_mul(2), any::<u64>()]
+}
+
+/// Runs `MockProver` at the smallest `k` that fits `circuit`, and fails the test case
+/// unless the circuit is satisfied exactly when `expected` is.
+pub fn check<F, C>(
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+    expected: bool,
+) -> Result<(), TestCaseError>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F>,
+{
+    let prover = min_k(circuit)
+        .and_then(|k| MockProver::run(k, circuit, instances))
+        .map_err(|e| TestCaseError::fail(format!("synthesis failed: {:?}", e)))?;
+    match prover.verify() {
+        Ok(()) if !expected => Err(TestCaseError::fail(
+            "satisfied, but the reference predicate does not hold",
+        )),
+        Err(failures) if expected => Err(TestCaseError::fail(format!(
+            "the reference predicate holds, but: {:?}",
+            failures
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{
+        example3::{FunctionChip, FunctionConfig},
+        example4::{self, FibonacciCircuit},
+    };
+    use crate::range_check::{
+        decompose_range_check::DecomposeRangeCheckCircuit, example1, example2, example3,
+    };
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Assigned, Column, ConstraintSystem, Error, Instance},
+    };
+
+    fn known(value: u64) -> Value<Assigned<Fp>> {
+        Value::known(Fp::from(value).into())
+    }
+
+    /// `FunctionChip` with its output exposed in the instance column, so that it can be
+    /// compared with the native function.
+    #[derive(Default)]
+    struct ExposedFunction {
+        a: Fp,
+        b: Fp,
+        c: Fp,
+    }
+
+    impl Circuit<Fp> for ExposedFunction {
+        type Config = (FunctionConfig<Fp>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let function = FunctionChip::configure(meta);
+            meta.enable_equality(function.output);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (function, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (function, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = FunctionChip::construct(function);
+            let out = chip.assign(layouter.namespace(|| "f"), self.a, self.b, self.c)?;
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    // the native reference of `FunctionChip`
+    fn function(a: Fp, b: Fp, c: Fp) -> Fp {
+        if a == b {
+            c
+        } else {
+            a - b
+        }
+    }
+
+    // a number of bits, and a value around `2^num_bits`
+    fn bits_and_value() -> impl Strategy<Value = (usize, u64)> {
+        (1usize..=12).prop_flat_map(|bits| (Just(bits), around(1 << bits)))
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        // `a == b` is picked half of the time as random elements are never equal, and the
+        // public output is a random element the other half
+        #[test]
+        fn property_function_circuit(
+            a in field::<Fp>(),
+            b in field::<Fp>(),
+            c in field::<Fp>(),
+            out in field::<Fp>(),
+            equal: bool,
+            honest: bool,
+        ) {
+            let b = if equal { a } else { b };
+            let out = if honest { function(a, b, c) } else { out };
+            let circuit = ExposedFunction { a, b, c };
+            check(&circuit, vec![vec![out]], out == function(a, b, c))?;
+        }
+
+        #[test]
+        fn property_range_check_1(value in around(8)) {
+            let circuit = example1::MyCircuit::<Fp, 8> { value: known(value) };
+            check(&circuit, vec![], value < 8)?;
+        }
+
+        #[test]
+        fn property_range_check_2(simple in around(8), lookup in around(256)) {
+            let circuit = example2::MyCircuit::<Fp, 8, 256> {
+                simple_value: known(simple),
+                lookup_value: known(lookup),
+            };
+            check(&circuit, vec![], simple < 8 && lookup < 256)?;
+        }
+
+        #[test]
+        fn property_range_check_3(num_bits in 0u8..=9, value in around(256)) {
+            let circuit = example3::MyCircuit::<Fp, 8, 256> {
+                num_bits: Value::known(num_bits),
+                value: known(value),
+            };
+            // 0 is tagged as a 1-bit value
+            let bits = (64 - value.leading_zeros()).max(1);
+            check(&circuit, vec![], value < 256 && u32::from(num_bits) == bits)?;
+        }
+
+        #[test]
+        fn property_decompose_range_check((num_bits, value) in bits_and_value()) {
+            let circuit = DecomposeRangeCheckCircuit::<Fp>::new(value.into(), num_bits);
+            check(&circuit, vec![], value < 1 << num_bits)?;
+        }
+
+        #[test]
+        fn property_fibonacci(
+            n in 1usize..50,
+            f0 in field::<Fp>(),
+            f1 in field::<Fp>(),
+            delta in field::<Fp>(),
+            tamper: bool,
+        ) {
+            // F(n) is off by `delta` half of the time
+            let delta = if tamper { delta } else { Fp::ZERO };
+            let circuit = FibonacciCircuit::<Fp>::new(n);
+            let mut instances = circuit.instances(f0, f1);
+            instances[0][example4::OUT_ROW] += delta;
+            check(&circuit, instances, delta == Fp::ZERO)?;
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// Real proving for the example circuits, instead of `MockProver::run`:
+//
+//   Params::new(k) -> keygen_vk -> keygen_pk -> create_proof -> verify_proof
+//
+// The commitment scheme is IPA over the Pasta curves (`EqAffine`, scalar field `Fp`),
+// and the Fiat-Shamir transcript is Blake2b.
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey,
+        SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+
+use crate::stats::min_k;
+
+/// The serialized transcript produced by [`Setup::prove`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof(pub Vec<u8>);
+
+impl Proof {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The result of checking a [`Proof`] against a verifying key and public inputs.
+#[derive(Debug)]
+pub enum Verification {
+    Accepted,
+    Rejected(Error),
+}
+
+impl Verification {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Verification::Accepted)
+    }
+}
+
+/// Public parameters and keys for one circuit shape.
+///
+/// The keys only depend on the circuit's configuration and fixed assignments, so a
+/// `Setup` can be reused for every witness of the same circuit.
+#[derive(Debug)]
+pub struct Setup {
+    pub k: u32,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+}
+
+impl Setup {
+    /// Generates `2^k`-row params, then the verifying and proving keys of `circuit`.
+    pub fn new<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<Self, Error> {
+        Self::from_params(k, Params::<EqAffine>::new(k), circuit)
+    }
+
+    /// Generates the keys of `circuit` with existing `2^k`-row params, e.g. loaded by
+    /// [`crate::artifact::load_setup`].
+    pub fn from_params<C: Circuit<Fp>>(
+        k: u32,
+        params: Params<EqAffine>,
+        circuit: &C,
+    ) -> Result<Self, Error> {
+        let empty_circuit = circuit.without_witnesses();
+        let vk = keygen_vk(&params, &empty_circuit)?;
+        let pk = keygen_pk(&params, vk, &empty_circuit)?;
+
+        Ok(Self { k, params, pk })
+    }
+
+    /// [`Setup::new`] with the smallest `k` that fits `circuit`.
+    pub fn with_min_k<C: Circuit<Fp>>(circuit: &C) -> Result<Self, Error> {
+        Self::new(min_k(circuit)?, circuit)
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<EqAffine> {
+        self.pk.get_vk()
+    }
+
+    /// Proves `circuit` for the given instance columns (one `Vec` per instance column).
+    ///
+    /// Like the real prover this does not check the witness: an unsatisfied circuit
+    /// produces a proof that [`Setup::verify`] rejects.
+    pub fn prove<C: Circuit<Fp>>(&self, circuit: C, instances: &[Vec<Fp>]) -> Result<Proof, Error> {
+        let instances: Vec<&[Fp]> = instances.iter().map(|column| column.as_slice()).collect();
+
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        create_proof(
+            &self.params,
+            &self.pk,
+            &[circuit],
+            &[&instances],
+            OsRng,
+            &mut transcript,
+        )?;
+
+        Ok(Proof(transcript.finalize()))
+    }
+
+    pub fn verify(&self, proof: &Proof, instances: &[Vec<Fp>]) -> Verification {
+        verify(&self.params, self.vk(), proof, instances)
+    }
+}
+
+/// Checks `proof` against a verifying key and the public inputs it was created for.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &Proof,
+    instances: &[Vec<Fp>],
+) -> Verification {
+    let instances: Vec<&[Fp]> = instances.iter().map(|column| column.as_slice()).collect();
+
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof.as_bytes());
+    match verify_proof(params, vk, strategy, &[&instances], &mut transcript) {
+        Ok(()) => Verification::Accepted,
+        Err(e) => Verification::Rejected(e),
+    }
+}
+
+/// Runs the whole pipeline for a single witness: setup, prove, then verify.
+pub fn prove_and_verify<C: Circuit<Fp>>(
+    k: u32,
+    circuit: C,
+    instances: &[Vec<Fp>],
+) -> Result<(Proof, Verification), Error> {
+    let setup = Setup::new(k, &circuit)?;
+    let proof = setup.prove(circuit, instances)?;
+    let verification = setup.verify(&proof, instances);
+
+    Ok((proof, verification))
+}
+
+/// [`prove_and_verify`] with the smallest `k` that fits `circuit`.
+pub fn prove_and_verify_with_min_k<C: Circuit<Fp>>(
+    circuit: C,
+    instances: &[Vec<Fp>],
+) -> Result<(Proof, Verification), Error> {
+    prove_and_verify(min_k(&circuit)?, circuit, instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{example1, example2, example3::FunctionCircuit};
+    use std::marker::PhantomData;
+
+    fn fibonacci_instances() -> Vec<Vec<Fp>> {
+        // F[0], F[1], F[9]
+        vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]
+    }
+
+    #[test]
+    fn prove_fibonacci_example1() {
+        let instances = fibonacci_instances();
+        let setup = Setup::with_min_k(&example1::MyCircuit::<Fp>(PhantomData)).unwrap();
+        let proof = setup
+            .prove(example1::MyCircuit(PhantomData), &instances)
+            .unwrap();
+        assert!(setup.verify(&proof, &instances).is_accepted());
+
+        // The same proof does not verify against a different output.
+        let mut tampered = instances.clone();
+        tampered[0][2] += Fp::one();
+        assert!(!setup.verify(&proof, &tampered).is_accepted());
+
+        // Nor can an honest prover prove the wrong output.
+        let proof = setup
+            .prove(example1::MyCircuit(PhantomData), &tampered)
+            .unwrap();
+        assert!(!setup.verify(&proof, &tampered).is_accepted());
+    }
+
+    #[test]
+    fn prove_fibonacci_example2() {
+        let instances = fibonacci_instances();
+        let (proof, verification) =
+            prove_and_verify(4, example2::MyCircuit::<Fp>(PhantomData), &instances).unwrap();
+        assert!(verification.is_accepted());
+        assert!(!proof.as_bytes().is_empty());
+
+        let mut tampered = instances;
+        tampered[0][0] = Fp::from(2);
+        let (_, verification) =
+            prove_and_verify(4, example2::MyCircuit::<Fp>(PhantomData), &tampered).unwrap();
+        assert!(!verification.is_accepted());
+    }
+
+    #[test]
+    fn prove_function_circuit() {
+        let circuit = FunctionCircuit {
+            a: Fp::from(10),
+            b: Fp::from(12),
+            c: Fp::from(15),
+        };
+        let (_, verification) = prove_and_verify_with_min_k(circuit, &[]).unwrap();
+        assert!(verification.is_accepted());
+    }
+}
@@ -1,5 +1,7 @@
-mod example1;
+pub mod chip;
+pub mod example1;
 mod example1b;
-mod example2;
-mod example3;
-mod decompose_range_check;
\ No newline at end of file
+pub mod example2;
+pub mod example3;
+pub mod decompose_range_check;
+pub mod table;
@@ -0,0 +1,274 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{decompose_range_check::DecomposeRangeCheckConfig, table::RangeTableConfig};
+
+// One range-check chip for every width: the number of bits is given on each call
+// instead of being a const generic of the config, and the strategy is picked from it.
+//
+//  - up to `max_poly_bits` bits: the polynomial gate of example1,
+//        v * (1 - v) * ... * (2^bits - 1 - v) = 0
+//    one gate per width, since the degree of the gate grows with the range.
+//  - up to `lookup_bits` bits: a single lookup of (bits, v) in the tagged table,
+//    like example2/example3.
+//  - more: the running sum of `decompose_range_check`, with `lookup_bits`-bit windows.
+//
+// All values share the `value` column and the single `2^(lookup_bits + 1) - 1` row table.
+//
+//        value     | q_poly[0] | q_poly[1] | q_range_check | window_num_bits |
+//       ----------------------------------------------------------------------
+//          v_0     |     1     |     0     |       0       |                 |   1-bit
+//          v_1     |     0     |     0     |       1       |        5        |   5-bit
+//           0      |     0     |     0     |       0       |        0        |
+
+/// Which constraints check a value of a given width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Polynomial,
+    Lookup,
+    RunningSum { windows: usize },
+}
+
+#[derive(Debug, Clone)]
+/// A range-constrained value in the circuit produced by the RangeCheckChip.
+pub struct RangeConstrained<F: PrimeFieldBits> {
+    pub cell: AssignedCell<F, F>,
+    pub num_bits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeCheckConfig<F: PrimeFieldBits> {
+    pub value: Column<Advice>,
+    /// `q_poly[i]` toggles the polynomial gate for `i + 1`-bit values.
+    pub q_poly: Vec<Selector>,
+    pub decompose: DecomposeRangeCheckConfig<F>,
+}
+
+impl<F: PrimeFieldBits> RangeCheckConfig<F> {
+    pub fn max_poly_bits(&self) -> usize {
+        self.q_poly.len()
+    }
+
+    pub fn lookup_bits(&self) -> usize {
+        self.decompose.window_bits
+    }
+
+    pub fn table(&self) -> &RangeTableConfig<F> {
+        &self.decompose.table
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeCheckChip<F: PrimeFieldBits> {
+    config: RangeCheckConfig<F>,
+}
+
+impl<F: PrimeFieldBits> RangeCheckChip<F> {
+    pub fn construct(config: RangeCheckConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &RangeCheckConfig<F> {
+        &self.config
+    }
+
+    /// `max_poly_bits` can be 0 to always use the lookup; keep it small, the degree of
+    /// the circuit becomes at least `2^max_poly_bits + 1`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        max_poly_bits: usize,
+        lookup_bits: usize,
+    ) -> RangeCheckConfig<F> {
+        assert!(
+            max_poly_bits <= lookup_bits,
+            "widths above lookup_bits always use the lookup"
+        );
+
+        let q_poly = (1..=max_poly_bits)
+            .map(|bits| {
+                let q_range_check = meta.selector();
+                meta.create_gate("range check", |meta| {
+                    let q = meta.query_selector(q_range_check);
+                    let value = meta.query_advice(value, Rotation::cur());
+
+                    // (v) * (1 - v) * (2 - v) * ... * (R - 1 - v)
+                    let range: u64 = 1 << bits;
+                    let range_check = (1..range).fold(value.clone(), |expr, i| {
+                        expr * (Expression::Constant(F::from(i)) - value.clone())
+                    });
+                    Constraints::with_selector(q, [("range check", range_check)])
+                });
+                q_range_check
+            })
+            .collect();
+
+        let decompose = DecomposeRangeCheckConfig::configure(meta, value, lookup_bits);
+
+        RangeCheckConfig {
+            value,
+            q_poly,
+            decompose,
+        }
+    }
+
+    /// Loads the shared lookup table, once per circuit.
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.table().load(layouter)
+    }
+
+    pub fn strategy(&self, num_bits: usize) -> Strategy {
+        assert!(num_bits > 0, "Empty value!");
+        if num_bits <= self.config.max_poly_bits() {
+            Strategy::Polynomial
+        } else if num_bits <= self.config.lookup_bits() {
+            Strategy::Lookup
+        } else {
+            Strategy::RunningSum {
+                windows: self.config.decompose.num_windows(num_bits),
+            }
+        }
+    }
+
+    /// Witnesses `value` and constrains it to `num_bits` bits.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+        num_bits: usize,
+    ) -> Result<RangeConstrained<F>, Error> {
+        let cell = match self.strategy(num_bits) {
+            Strategy::Polynomial => layouter.assign_region(
+                || "Assign value for simple range check",
+                |mut region| {
+                    self.config.q_poly[num_bits - 1].enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", self.config.value, 0, || value)
+                },
+            )?,
+            Strategy::Lookup | Strategy::RunningSum { .. } => {
+                self.config.decompose.assign(layouter, value, num_bits)?.0
+            }
+        };
+
+        Ok(RangeConstrained { cell, num_bits })
+    }
+
+    /// Constrains an already assigned cell to `num_bits` bits.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<RangeConstrained<F>, Error> {
+        let cell = match self.strategy(num_bits) {
+            Strategy::Polynomial => layouter.assign_region(
+                || "Copy value for simple range check",
+                |mut region| {
+                    self.config.q_poly[num_bits - 1].enable(&mut region, 0)?;
+                    cell.copy_advice(|| "value", &mut region, self.config.value, 0)
+                },
+            )?,
+            Strategy::Lookup | Strategy::RunningSum { .. } => {
+                self.config.decompose.copy_check(layouter, cell, num_bits)?.0
+            }
+        };
+
+        Ok(RangeConstrained { cell, num_bits })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::stats::min_k;
+
+    const MAX_POLY_BITS: usize = 2;
+    const LOOKUP_BITS: usize = 4;
+
+    /// Checks each `(value, num_bits)` pair, all in the same circuit.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeFieldBits> {
+        values: Vec<(Value<F>, usize)>,
+    }
+
+    impl<F: PrimeFieldBits> Circuit<F> for MyCircuit<F> {
+        type Config = RangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: self
+                    .values
+                    .iter()
+                    .map(|(_, num_bits)| (Value::unknown(), *num_bits))
+                    .collect(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let value = meta.advice_column();
+            RangeCheckChip::configure(meta, value, MAX_POLY_BITS, LOOKUP_BITS)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            for (value, num_bits) in self.values.iter() {
+                chip.assign(layouter.namespace(|| "range check"), *value, *num_bits)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(values: &[(u64, usize)]) -> MyCircuit<Fp> {
+        MyCircuit {
+            values: values
+                .iter()
+                .map(|(value, num_bits)| (Value::known(Fp::from(*value)), *num_bits))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_strategy() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let chip = RangeCheckChip::construct(MyCircuit::<Fp>::configure(&mut meta));
+
+        assert_eq!(chip.strategy(1), Strategy::Polynomial);
+        assert_eq!(chip.strategy(2), Strategy::Polynomial);
+        assert_eq!(chip.strategy(3), Strategy::Lookup);
+        assert_eq!(chip.strategy(4), Strategy::Lookup);
+        assert_eq!(chip.strategy(5), Strategy::RunningSum { windows: 2 });
+        assert_eq!(chip.strategy(12), Strategy::RunningSum { windows: 3 });
+    }
+
+    #[test]
+    fn test_range_check_chip() {
+        // Every width, from the polynomial gate to three lookup windows, in one circuit.
+        for num_bits in 1..=12 {
+            let max = (1 << num_bits) - 1;
+            let circuit = circuit(&[(0, num_bits), (max / 2, num_bits), (max, num_bits), (1, 12)]);
+            let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Out-of-range values, next to valid ones of other widths.
+        for num_bits in 1..=12 {
+            let circuit = circuit(&[(3, 2), (1 << num_bits, num_bits), (200, 8)]);
+            let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "2^{} is not a {}-bit value", num_bits, num_bits);
+        }
+    }
+}
@@ -1,21 +1,14 @@
-use ff::{Field, PrimeField};
+use ff::{Field, PrimeFieldBits};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
-    plonk::{
-        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
-        Selector,
-    },
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
     poly::Rotation,
 };
-use std::marker::PhantomData;
 
-// create a submodule which is my table and use that
-mod table;
-use table::*;
+use super::table::RangeTableConfig;
 
 /// Decomposes an $n$-bit Primefield element $\alpha$ into $W$ windows, each window
 /// being a $K$-bit word, using a running sum $z$.
-/// We constrain $K \leq 3$ for this helper.
 ///     $$\alpha = k_0 + (2^K) k_1 + (2^{2K}) k_2 + ... + (2^{(W-1)K}) k_{W-1}$$
 ///
 /// $z_0$ is initialized as $\alpha$. Each successive $z_{i+1}$ is computed as
@@ -23,177 +16,250 @@ use table::*;
 /// $z_W$ is constrained to be zero.
 /// The difference between each interstitial running sum output is constrained
 /// to be $K$ bits, i.e.
-///                      `range_check`($k_i$, $2^K$),
-/// where
-/// ```text
-///   range_check(word)
-///     = word * (1 - word) * (2 - word) * ... * ((range - 1) - word)
-/// ```
+///                      $k_i = z_i - 2^K z_{i+1}$ is looked up in a $K$-bit table.
 ///
-/// Given that the `range_check` constraint will be toggled by a selector, in
-/// practice we will have a `selector * range_check(word)` expression
-/// of degree `range + 1`.
+/// When $n$ is not a multiple of $K$, the last window only has $n - (W-1)K$ bits, and
+/// is looked up with that tighter bound (the table is tagged by bit length), so that
+/// the check is strict: $\alpha < 2^n$, not just $\alpha < 2^{WK}$.
 ///
-/// This means that $2^K$ has to be at most `degree_bound - 1` in order for
-/// the range check constraint to stay within the degree bound.
+/// ```text
+///   running_sum | window_num_bits | q_range_check | q_final
+///  -------------+-----------------+---------------+---------
+///       z_0     |        K        |       1       |    0
+///       z_1     |        K        |       1       |    0
+///       ...     |       ...       |      ...      |   ...
+///     z_{W-1}   |  n - (W-1)K     |       1       |    0
+///       z_W     |        0        |       0       |    1
+/// ```
 ///
-/// This is a custom built version of the decompose running sum function.
-
+/// Compared to checking $\alpha = \sum_i 2^{iK} k_i$ in a single gate, every constraint
+/// here only queries two consecutive rows, whatever the number of windows.
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the DecomposeRangeCheckConfig.
-struct RangeConstrained<F: PrimeField>(AssignedCell<F, F>);
-
-// RANGE is the size of the total range we want to check.
-// LOOKUP_RANGE is the size of our lookup table i.e. the max size we can lookup in one check to the table.
-// NUM_BITS is the max number of bits we want to use to represent each value in the lookup range.
-const RANGE: usize = 64;
-const NUM_BITS: usize = 3;
-const LOOKUP_RANGE: usize = 8;
-// Thus, here we decompose a number into 3-bit chunks.
+pub struct RangeConstrained<F: PrimeFieldBits>(pub AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct DecomposeRangeCheckConfig<F: PrimeField> {
-    value: Column<Advice>,
-    value_decomposed: Column<Advice>, // Assume this value perfectly decomposes
-    q_decomposed: Selector,
-    q_range_check: Selector,
-    table: RangeTableConfig<F, LOOKUP_RANGE>,
-    _marker: PhantomData<F>,
+pub struct DecomposeRangeCheckConfig<F: PrimeFieldBits> {
+    pub running_sum: Column<Advice>,
+    pub window_num_bits: Column<Fixed>,
+    pub q_range_check: Selector,
+    pub q_final: Selector,
+    pub table: RangeTableConfig<F>,
+    /// $K$, the number of bits of a full window.
+    pub window_bits: usize,
 }
 
-impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
-    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let value = meta.advice_column();
-        let value_decomposed = meta.advice_column();
-        let q_decomposed = meta.selector();
+impl<F: PrimeFieldBits> DecomposeRangeCheckConfig<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        running_sum: Column<Advice>,
+        window_bits: usize,
+    ) -> Self {
+        assert!(window_bits > 0 && window_bits < 64);
+
+        let window_num_bits = meta.fixed_column();
         let q_range_check = meta.complex_selector();
-        let table = RangeTableConfig::configure(meta);
-        //        value     |  value_decomposed |    q_decomposed      |   q_range_check   | range_check_table
-        //       ---------------------------------------------------------------------------------------------
-        //          v       |         v_0       |          1           |         1         |        0
-        //          -       |         v_1       |          0           |         1         |        1
-        //          -       |         v_2       |          0           |         1         |        2
-
-        // Lookup each decomposed value individually, not paying attention to bit count
+        let q_final = meta.selector();
+        let table = RangeTableConfig::configure(meta, window_bits);
+
+        meta.enable_equality(running_sum);
+
+        // k_i = z_i - 2^K * z_{i+1} must be a `window_num_bits`-bit value.
+        // When q_range_check = 0 this looks up (0, 0), which is in the table.
         meta.lookup(|meta| {
             let q = meta.query_selector(q_range_check);
-            let decomposed_value = meta.query_advice(value_decomposed, Rotation::cur());
-            vec![(q.clone() * decomposed_value, table.value)]
-        });
+            let z_cur = meta.query_advice(running_sum, Rotation::cur());
+            let z_next = meta.query_advice(running_sum, Rotation::next());
+            let num_bits = meta.query_fixed(window_num_bits, Rotation::cur());
 
-        // Ensure that the decomposed values add up to the original value
-        meta.create_gate("decompose", |meta| {
-            let q = meta.query_selector(q_decomposed);
-            let value = meta.query_advice(value, Rotation::cur());
-            let mut decomposed_values = vec![];
-            let decomposed_parts = RANGE / LOOKUP_RANGE;
-
-            // Because we rotate up to 8 times here, this gate adds a lot of overhead.
-            // It would be much more efficient to also have a prefix sum at each step,
-            // and only cover 1-2 different rotations instead per constraint
-            for i in 0..decomposed_parts {
-                decomposed_values.push(meta.query_advice(value_decomposed, Rotation(i as i32)));
-            }
+            let word = z_cur - z_next * Expression::Constant(F::from(1u64 << window_bits));
+            vec![(q.clone() * num_bits, table.num_bits), (q * word, table.value)]
+        });
 
-            // Combines the decomposed values into a single value and asserts equality
-            let decomposed_check =
-                |decomposed_parts: usize,
-                 value: Expression<F>,
-                 decomposed_values: Vec<Expression<F>>| {
-                    assert!(decomposed_parts > 0, "Empty value!");
-                    assert!(
-                        NUM_BITS * decomposed_parts < 64,
-                        "Value doesn't fit in bits!"
-                    );
-                    const multiplier: usize = 1 << NUM_BITS;
-                    (0..decomposed_parts).fold(
-                        Expression::Constant(F::from(0 as u64)),
-                        |expr, i| {
-                            expr + decomposed_values[i].clone()
-                                * Expression::Constant(F::from(1_u64 << (NUM_BITS * i)))
-                        },
-                    ) - value
-                };
-
-            Constraints::with_selector(
-                q,
-                [(
-                    "range check",
-                    decomposed_check(decomposed_parts, value, decomposed_values),
-                )],
-            )
+        // z_W = 0, i.e. there is nothing left after the last window.
+        meta.create_gate("final window", |meta| {
+            let q = meta.query_selector(q_final);
+            let z = meta.query_advice(running_sum, Rotation::cur());
+            vec![q * z]
         });
 
         Self {
-            value,
-            value_decomposed,
-            q_decomposed,
+            running_sum,
+            window_num_bits,
             q_range_check,
+            q_final,
             table,
-            _marker: PhantomData,
+            window_bits,
         }
     }
 
-    // Note that the two types of region.assign_advice calls happen together so that it is the same region
-    pub fn assign_value(&self, mut layouter: impl Layouter<F>, value: u128) -> Result<bool, Error> {
+    /// Witnesses `value` and constrains it to `num_bits` bits.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+        num_bits: usize,
+    ) -> Result<RangeConstrained<F>, Error> {
+        layouter.assign_region(
+            || "Assign value",
+            |mut region| {
+                let z_0 = region.assign_advice(|| "z_0", self.running_sum, 0, || value)?;
+                self.decompose(&mut region, z_0.clone(), num_bits)?;
+                Ok(RangeConstrained(z_0))
+            },
+        )
+    }
+
+    /// Constrains an already assigned cell to `num_bits` bits.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<RangeConstrained<F>, Error> {
+        layouter.assign_region(
+            || "Copy value",
+            |mut region| {
+                let z_0 = cell.copy_advice(|| "z_0", &mut region, self.running_sum, 0)?;
+                self.decompose(&mut region, z_0.clone(), num_bits)?;
+                Ok(RangeConstrained(z_0))
+            },
+        )
+    }
+
+    /// Like [`Self::assign`], but returns the whole running sum $z_0, ..., z_W$. With
+    /// 1-bit windows $z_i$ is `value >> i`, and bit $i$ is $z_i - 2 z_{i+1}$.
+    pub fn assign_running_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
         layouter.assign_region(
             || "Assign value",
             |mut region| {
-                let offset = 0;
-
-                // Enable q_range_check
-                self.q_decomposed.enable(&mut region, offset)?;
-
-                // Assign value
-                region.assign_advice(
-                    || "value",
-                    self.value,
-                    offset,
-                    || Value::known(F::from_u128(value)),
-                )?;
-
-                // Enable q_decomposed
-                let decomposed_parts = RANGE / LOOKUP_RANGE;
-                let mut decompose_in_progress = value;
-                for i in 0..decomposed_parts {
-                    // offset = i;
-                    self.q_range_check.enable(&mut region, i)?;
-                    let decomposed_val = decompose_in_progress % { 1 << (i * NUM_BITS) };
-                    region.assign_advice(
-                        || format!("decomposed_value {:?}", i),
-                        self.value_decomposed,
-                        i,
-                        || Value::known(F::from_u128(decomposed_val)),
-                    )?;
-                    decompose_in_progress = decompose_in_progress >> (i * NUM_BITS);
-                }
-                Ok(true)
+                let z_0 = region.assign_advice(|| "z_0", self.running_sum, 0, || value)?;
+                self.decompose(&mut region, z_0, num_bits)
             },
         )
     }
+
+    /// Number of windows used to check a `num_bits`-bit value, i.e. $W = \lceil n / K \rceil$.
+    pub fn num_windows(&self, num_bits: usize) -> usize {
+        (num_bits + self.window_bits - 1) / self.window_bits
+    }
+
+    // Assigns z_1..=z_W below z_0 (already at offset 0) and the window tags. Returns
+    // z_0..=z_W.
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        z_0: AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(num_bits > 0, "Empty value!");
+        assert!(
+            num_bits < F::NUM_BITS as usize,
+            "Value doesn't fit in bits!"
+        );
+
+        let num_windows = self.num_windows(num_bits);
+        let last_window_bits = num_bits - (num_windows - 1) * self.window_bits;
+
+        let words = z_0
+            .value()
+            .map(|v| decompose_word(v, num_windows, self.window_bits));
+        let two_pow_k_inv = F::from(1u64 << self.window_bits).invert().unwrap();
+
+        let mut zs = vec![z_0];
+        for i in 0..num_windows {
+            self.q_range_check.enable(region, i)?;
+            let window_num_bits = if i == num_windows - 1 {
+                last_window_bits
+            } else {
+                self.window_bits
+            };
+            region.assign_fixed(
+                || format!("window {} num_bits", i),
+                self.window_num_bits,
+                i,
+                || Value::known(F::from(window_num_bits as u64)),
+            )?;
+
+            // z_{i+1} = (z_i - k_i) / 2^K
+            let word = words.as_ref().map(|words| F::from(words[i]));
+            let z_next = (zs[i].value().copied() - word) * Value::known(two_pow_k_inv);
+            let z = region.assign_advice(
+                || format!("z_{}", i + 1),
+                self.running_sum,
+                i + 1,
+                || z_next,
+            )?;
+            zs.push(z);
+        }
+
+        self.q_final.enable(region, num_windows)?;
+        region.assign_fixed(
+            || "final num_bits",
+            self.window_num_bits,
+            num_windows,
+            || Value::known(F::ZERO),
+        )?;
+
+        Ok(zs)
+    }
+}
+
+/// Splits the low `num_windows * window_bits` bits of `value` into little-endian words.
+/// Higher bits are ignored, and are caught by the `z_W = 0` constraint.
+fn decompose_word<F: PrimeFieldBits>(value: &F, num_windows: usize, window_bits: usize) -> Vec<u64> {
+    let bits: Vec<bool> = value
+        .to_le_bits()
+        .iter()
+        .by_vals()
+        .take(num_windows * window_bits)
+        .collect();
+
+    bits.chunks(window_bits)
+        .map(|chunk| chunk.iter().rev().fold(0, |acc, bit| (acc << 1) + *bit as u64))
+        .collect()
 }
+
+/// Size $K$ of the windows used by [`DecomposeRangeCheckCircuit`].
+pub const WINDOW_BITS: usize = 3;
+
 #[derive(Default, Clone)]
-struct DecomposeRangeCheckCircuit<F: PrimeField> {
-    // Since this is only relevant for the witness, we can opt to make this whatever convenient type we want
-    pub value: u128,
-    _marker: PhantomData<F>,
+pub struct DecomposeRangeCheckCircuit<F: PrimeFieldBits> {
+    pub value: Value<F>,
+    /// The value must fit in `num_bits` bits; this does not have to be a multiple of
+    /// `WINDOW_BITS`.
+    pub num_bits: usize,
 }
 
-impl<F: PrimeField> Circuit<F> for DecomposeRangeCheckCircuit<F> {
+impl<F: PrimeFieldBits> DecomposeRangeCheckCircuit<F> {
+    pub fn new(value: u128, num_bits: usize) -> Self {
+        Self {
+            value: Value::known(F::from_u128(value)),
+            num_bits,
+        }
+    }
+}
+
+impl<F: PrimeFieldBits> Circuit<F> for DecomposeRangeCheckCircuit<F> {
     type Config = DecomposeRangeCheckConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
     // Circuit without witnesses, called only during key generation
     fn without_witnesses(&self) -> Self {
         Self {
-            value: 0,
-            _marker: PhantomData,
+            value: Value::unknown(),
+            num_bits: self.num_bits,
         }
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let config = DecomposeRangeCheckConfig::configure(meta);
-        config
+        let running_sum = meta.advice_column();
+        DecomposeRangeCheckConfig::configure(meta, running_sum, WINDOW_BITS)
     }
 
     fn synthesize(
@@ -202,80 +268,74 @@ impl<F: PrimeField> Circuit<F> for DecomposeRangeCheckCircuit<F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         config.table.load(&mut layouter)?;
-        print!("Synthesize being called...\n");
-        assert!(
-            RANGE % LOOKUP_RANGE == 0,
-            "Range must be a multiple of lookup range"
-        );
-        let mut value = config.assign_value(layouter.namespace(|| "Assign all values"), self.value);
+        config.assign(
+            layouter.namespace(|| "Assign all values"),
+            self.value,
+            self.num_bits,
+        )?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     use super::*;
+    use crate::diagnostics::{diagnose, expect_failure};
+    use crate::stats::min_k;
 
     #[test]
     fn test_range_check_pass() {
-        let k = 10; // 8, 128, etc
-
-        // Successful cases
-        for i in 0..RANGE {
-            let i = 0;
-            let circuit = DecomposeRangeCheckCircuit::<Fp> {
-                value: i as u128,
-                _marker: PhantomData,
-            };
+        // 8 bits = 3 + 3 + 2, 9 bits = 3 + 3 + 3
+        for num_bits in [8, 9] {
+            for i in 0..(1u128 << num_bits) {
+                let circuit = DecomposeRangeCheckCircuit::<Fp>::new(i, num_bits);
 
-            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-            prover.assert_satisfied();
+                let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
+                prover.assert_satisfied();
+            }
         }
     }
 
     #[test]
     fn test_range_check_fail() {
-        let k = 10;
-        // Out-of-range `value = 8`
-        let circuit = DecomposeRangeCheckCircuit::<Fp> {
-            value: RANGE as u128,
-            _marker: PhantomData,
-        };
-        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-        match prover.verify() {
-            Err(e) => {
-                println!("Error successfully achieved!");
+        for num_bits in [1, 2, 3, 8, 9] {
+            for value in [1u128 << num_bits, (1 << num_bits) + 1, 1 << (num_bits + 3), u64::MAX as u128] {
+                // either a window or the final running sum is out of range
+                let circuit = DecomposeRangeCheckCircuit::<Fp>::new(value, num_bits);
+                let report = diagnose(&circuit, vec![]).unwrap();
+                expect_failure().in_region("Assign value").assert(&report);
             }
-            _ => assert_eq!(1, 0),
         }
+
+        // -1 = p - 1 has all of its low bits set
+        let circuit = DecomposeRangeCheckCircuit {
+            value: Value::known(-Fp::one()),
+            num_bits: 9,
+        };
+        let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_decompose_word() {
+        // 0b10_110_001
+        assert_eq!(decompose_word(&Fp::from(0b10110001), 3, 3), vec![0b001, 0b110, 0b10]);
+        assert_eq!(decompose_word(&Fp::from(511), 3, 3), vec![7, 7, 7]);
+        // bits above num_windows * window_bits are dropped
+        assert_eq!(decompose_word(&Fp::from(512), 3, 3), vec![0, 0, 0]);
     }
 
     // $ cargo test --release --all-features print_decompose_range_check_1
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_decompose_range_check_1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-decomposed-layout.png", (1024, 3096))
-            .into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 1 Layout", ("sans-serif", 60))
-            .unwrap();
-
-        let circuit = DecomposeRangeCheckCircuit::<Fp> {
-            value: 2 as u128,
-            _marker: PhantomData,
+        let options = crate::layout::LayoutOptions {
+            title: Some("Decompose Range Check Layout".into()),
+            ..Default::default()
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
-            .unwrap();
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(2, 8);
+        crate::layout::render_to_file(&circuit, "range-check-decomposed-layout.png", &options).unwrap();
     }
-}
\ No newline at end of file
+}
@@ -2,8 +2,11 @@ use std::marker::PhantomData;
 use ff::{Field, PrimeField};
 use halo2_proofs::{
     // arithmetic::FieldExt,
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
+        Selector,
+    },
     poly::Rotation,
 };
 
@@ -16,12 +19,12 @@ use halo2_proofs::{
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: PrimeField, const RANGE: usize>(pub AssignedCell<Assigned<F>, F>);
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: PrimeField, const RANGE: usize> {
-    value: Column<Advice>,
-    q_range_check: Selector,
+pub struct RangeCheckConfig<F: PrimeField, const RANGE: usize> {
+    pub value: Column<Advice>,
+    pub q_range_check: Selector,
     _marker: PhantomData<F>,
 }
 
@@ -85,50 +88,50 @@ impl<F: PrimeField, const RANGE: usize> RangeCheckConfig<F, RANGE> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+#[derive(Default)]
+pub struct MyCircuit<F: PrimeField, const RANGE: usize> {
+    pub value: Value<Assigned<F>>,
+}
 
-    use super::*;
+impl<F: PrimeField, const RANGE: usize> Circuit<F> for MyCircuit<F, RANGE> {
+    type Config = RangeCheckConfig<F, RANGE>;
+    type FloorPlanner = V1;
 
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const RANGE: usize> {
-        value: Value<Assigned<F>>,
+    fn without_witnesses(&self) -> Self {
+        Self::default()
     }
 
-    impl<F: PrimeField, const RANGE: usize> Circuit<F> for MyCircuit<F, RANGE> {
-        type Config = RangeCheckConfig<F, RANGE>;
-        type FloorPlanner = V1;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.assign(layouter.namespace(|| "Assign value"), self.value)?;
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, value)
-        }
+        Ok(())
+    }
+}
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            config.assign(layouter.namespace(|| "Assign value"), self.value)?;
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::Any,
+    };
 
-            Ok(())
-        }
-    }
+    use super::*;
+    use crate::stats::min_k;
 
     #[test]
     fn test_range_check_1() {
-        let k = 4;
         const RANGE: usize = 8; // 3-bit value
+        let k = min_k(&MyCircuit::<Fp, RANGE>::default()).unwrap();
 
         // Successful cases
         for i in 0..RANGE {
@@ -164,19 +167,13 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 1 Layout", ("sans-serif", 60))
-            .unwrap();
-
+        let options = crate::layout::LayoutOptions {
+            title: Some("Range Check 1 Layout".into()),
+            ..Default::default()
+        };
         let circuit = MyCircuit::<Fp, 8> {
             value: Value::unknown(),
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "range-check-1-layout.png", &options).unwrap();
     }
 }
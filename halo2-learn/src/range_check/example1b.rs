@@ -177,11 +177,12 @@ mod tests {
     };
 
     use super::*;
+    use crate::stats::min_k;
 
     #[test]
     fn test_range_check_1() {
-        let k = 4; //2^k rows
         const RANGE: usize = 8; // 3-bit value
+        let k = min_k(&MyCircuit::<Fp, RANGE>::default()).unwrap(); //2^k rows
         let testvalue: u64 = 22;
 
         // Successful cases
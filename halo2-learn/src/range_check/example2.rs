@@ -1,11 +1,14 @@
 use ff::{Field, PrimeField};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
+        Selector,
+    },
     poly::Rotation,
 };
 
-mod table;
+pub mod table;
 use table::*;
 
 // This helper checks that the value witnessed in a given cell is within a given range.
@@ -21,14 +24,14 @@ use table::*;
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: PrimeField, const RANGE: usize>(pub AssignedCell<Assigned<F>, F>);
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
-    q_range_check: Selector,
-    q_lookup: Selector,
-    value: Column<Advice>,
-    table: RangeTableConfig<F, LOOKUP_RANGE>,  // Lookup table
+pub struct RangeCheckConfig<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
+    pub q_range_check: Selector,
+    pub q_lookup: Selector,
+    pub value: Column<Advice>,
+    pub table: RangeTableConfig<F, LOOKUP_RANGE>,  // Lookup table
 }
 
 // Write the gate for our range check Config
@@ -139,66 +142,67 @@ impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize>
     }
 }
 
+#[derive(Default)]
+pub struct MyCircuit<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
+    pub simple_value: Value<Assigned<F>>,
+    pub lookup_value: Value<Assigned<F>>,
+}
+
+impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F>
+    for MyCircuit<F, RANGE, LOOKUP_RANGE>
+{
+    type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self { Self::default() }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // load lookup table.
+        config.table.load(&mut layouter)?;
+
+        config.assign_simple(
+            layouter.namespace(|| "Assign simple(smaller) value"), 
+            self.simple_value
+        )?;
+        config.assign_lookup(
+            layouter.namespace(|| "Assign lookup(larger) value"),
+            self.lookup_value,
+        )?;
+        Ok(())
+    }
+}
+
 // [cfg(test)]是一个条件编译属性，意思是只有在执行 test 时，此模块代码才会被编译和执行
 // 好处是，当你在普通的编译或生产环境下构建你的程序时，测试代码不会被包括进去，
 // 从而减少了编译时间和生成的可执行文件的大小。
 #[cfg(test)]
 mod tests {
     use halo2_proofs::{
-        circuit::floor_planner::V1,
         dev::{FailureLocation, MockProver, VerifyFailure},
         pasta::Fp,
-        plonk::{Any, Circuit},
+        plonk::Any,
     };
 
     use super::*;
-
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
-        simple_value: Value<Assigned<F>>,
-        lookup_value: Value<Assigned<F>>,
-    }
-
-    impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F>
-        for MyCircuit<F, RANGE, LOOKUP_RANGE>
-    {
-        type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
-        type FloorPlanner = V1;
-
-        fn without_witnesses(&self) -> Self { Self::default() }
-
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, value)
-        }
-
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            // load lookup table.
-            config.table.load(&mut layouter)?;
-
-            config.assign_simple(
-                layouter.namespace(|| "Assign simple(smaller) value"), 
-                self.simple_value
-            )?;
-            config.assign_lookup(
-                layouter.namespace(|| "Assign lookup(larger) value"),
-                self.lookup_value,
-            )?;
-            Ok(())
-        }
-    }
+    use crate::stats::min_k;
 
     #[test]
     fn test_range_check_2() {
-        // in every circuit, we opt to reserve the last few rows of each advice cols 
-        // for random values which are blinding factors(for zk), so `k` is always larger.
-        let k = 9;
         const RANGE: usize = 8; // 3-bit value
         const LOOKUP_RANGE: usize = 256; // 2^8, 8-bit value
+        // in every circuit, we opt to reserve the last few rows of each advice cols 
+        // for random values which are blinding factors(for zk), so `k` is always larger
+        // than log2 of the 256 table rows: 9 here.
+        let k = min_k(&MyCircuit::<Fp, RANGE, LOOKUP_RANGE>::default()).unwrap();
 
         // Successful cases
         for i in 0..RANGE {
@@ -249,20 +253,14 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_2() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-2-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 2 Layout", ("sans-serif", 60))
-            .unwrap();
-
+        let options = crate::layout::LayoutOptions {
+            title: Some("Range Check 2 Layout".into()),
+            ..Default::default()
+        };
         let circuit = MyCircuit::<Fp, 8, 256> {
             simple_value: Value::unknown(),
             lookup_value: Value::unknown(),
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(9, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "range-check-2-layout.png", &options).unwrap();
     }
 }
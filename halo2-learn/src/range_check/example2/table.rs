@@ -10,18 +10,17 @@ use halo2_proofs::{
     plonk::{ConstraintSystem, Error, TableColumn},
 };
 
-/// pub(super) 仅当前模块的父模块中可见，但不对外公开
 /// A lookup table of values from 0..RANGE. 
 /// TableColumn is a Fixed Column
 #[derive(Debug, Clone)]
-pub(super) struct RangeTableConfig<F: PrimeField, const RANGE: usize> {
-    pub(super) value: TableColumn, 
+pub struct RangeTableConfig<F: PrimeField, const RANGE: usize> {
+    pub value: TableColumn, 
     // 这个 struct 中存在一个与类型 F 相关的关联，即使 struct 自身并没有实际使用这个类型
     _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         // API to create this special fixed colum
         let value = meta.lookup_table_column();
 
@@ -33,7 +32,7 @@ impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
 
     // load function assign the values to our fixed table
     // This action is performed at key gen time
-    pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         // firstly, for some RANGE we want to load all the values and assign it to the lookup table
         // assign_table is a special api that only works for lookup tables
         layouter.assign_table (
@@ -1,12 +1,12 @@
 use ff::{Field, PrimeField};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
     poly::Rotation,
 };
 
 // create a submodule which is my table and use that
-mod table;
+pub mod table;
 use table::*;
 
 // /// This helper uses a lookup table to check that the value witnessed in a given cell is
@@ -27,18 +27,18 @@ use table::*;
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField> {
-    num_bits: AssignedCell<Assigned<F>, F>,
-    assigned_cell: AssignedCell<Assigned<F>, F>,
+pub struct RangeConstrained<F: PrimeField> {
+    pub num_bits: AssignedCell<Assigned<F>, F>,
+    pub assigned_cell: AssignedCell<Assigned<F>, F>,
 }
 
 #[derive(Debug, Clone)]
 // WE ADD A FURTHER NUM_BITS COLUMN TO OUR CONFIG
-struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
-    q_lookup: Selector,
-    num_bits: Column<Advice>,
-    value: Column<Advice>,
-    table: RangeTableConfig<F, NUM_BITS, RANGE>,
+pub struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+    pub q_lookup: Selector,
+    pub num_bits: Column<Advice>,
+    pub value: Column<Advice>,
+    pub table: RangeTableConfig<F, NUM_BITS, RANGE>,
 }
 
 // Write the gate for our range check Config
@@ -132,56 +132,57 @@ impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> RangeCheckConfig<
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
+#[derive(Default)]
+pub struct MyCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+    pub num_bits: Value<u8>,
+    pub value: Value<Assigned<F>>,
+}
 
-    use super::*;
+impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
+    for MyCircuit<F, NUM_BITS, RANGE>
+{
+    type Config = RangeCheckConfig<F, NUM_BITS, RANGE>;
+    type FloorPlanner = V1;
 
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
-        num_bits: Value<u8>,
-        value: Value<Assigned<F>>,
+    fn without_witnesses(&self) -> Self {
+        Self::default()
     }
 
-    impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
-        for MyCircuit<F, NUM_BITS, RANGE>
-    {
-        type Config = RangeCheckConfig<F, NUM_BITS, RANGE>;
-        type FloorPlanner = V1;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let num_bits = meta.advice_column();
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, num_bits, value)
+    }
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter)?;
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let num_bits = meta.advice_column();
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, num_bits, value)
-        }
+        config.assign(
+            layouter.namespace(|| "Assign value"),
+            self.num_bits,
+            self.value,
+        )?;
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            config.table.load(&mut layouter)?;
+        Ok(())
+    }
+}
 
-            config.assign(
-                layouter.namespace(|| "Assign value"),
-                self.num_bits,
-                self.value,
-            )?;
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
-            Ok(())
-        }
-    }
+    use super::*;
+    use crate::stats::min_k;
 
     #[test]
     fn test_range_check_3() {
-        let k = 9;
         const NUM_BITS: usize = 8;
         const RANGE: usize = 256; // 8-bit value
+        let k = min_k(&MyCircuit::<Fp, NUM_BITS, RANGE>::default()).unwrap();
 
         // Successful cases
         for num_bits in 1u8..=NUM_BITS.try_into().unwrap() {
@@ -201,20 +202,14 @@ mod tests {
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_3() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-3-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 3 Layout", ("sans-serif", 60))
-            .unwrap();
-
+        let options = crate::layout::LayoutOptions {
+            title: Some("Range Check 3 Layout".into()),
+            ..Default::default()
+        };
         let circuit = MyCircuit::<Fp, 8, 256> {
             num_bits: Value::unknown(),
             value: Value::unknown(),
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(9, &circuit, &root)
-            .unwrap();
+        crate::layout::render_to_file(&circuit, "range-check-3-layout.png", &options).unwrap();
     }
 }
@@ -9,14 +9,14 @@ use halo2_proofs::{
 /// e.g. RANGE = 256, values = [0..255]
 /// This table is tagged by an index `k`, where `k` is the number of bits of the element in the `value` column.
 #[derive(Debug, Clone)]
-pub(super) struct RangeTableConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
-    pub(super) num_bits: TableColumn, // tag for our table.
-    pub(super) value: TableColumn,
+pub struct RangeTableConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+    pub num_bits: TableColumn, // tag for our table.
+    pub value: TableColumn,
     _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> RangeTableConfig<F, NUM_BITS, RANGE> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         assert_eq!(1 << NUM_BITS, RANGE);  //左移一位 why? 1:00:06
 
         let num_bits = meta.lookup_table_column();
@@ -29,7 +29,7 @@ impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> RangeTableConfig<
         }
     }
 
-    pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         layouter.assign_table(
             || "load range-check table",
             |mut table| {
@@ -0,0 +1,75 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error, TableColumn},
+};
+
+/// A lookup table of `(num_bits, value)` for every `value < 2^num_bits`,
+/// with `num_bits` in `0..=max_bits`.
+///
+///   num_bits | value
+///  ----------+-------
+///      0     |   0
+///      1     |   0
+///      1     |   1
+///      2     |   0
+///     ...    |  ...
+///
+/// Looking up `(b, v)` proves that `v` fits in `b` bits. `(0, 0)` is the
+/// input of the lookup on rows where it is disabled.
+#[derive(Debug, Clone)]
+pub struct RangeTableConfig<F: PrimeField> {
+    pub num_bits: TableColumn,
+    pub value: TableColumn,
+    pub max_bits: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeTableConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, max_bits: usize) -> Self {
+        let num_bits = meta.lookup_table_column();
+        let value = meta.lookup_table_column();
+
+        Self {
+            num_bits,
+            value,
+            max_bits,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of rows taken by the table, `2^(max_bits + 1) - 1`.
+    pub fn num_rows(&self) -> usize {
+        (1 << (self.max_bits + 1)) - 1
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load range-check table",
+            |mut table| {
+                let mut offset = 0;
+                for num_bits in 0..=self.max_bits {
+                    for value in 0..(1u64 << num_bits) {
+                        table.assign_cell(
+                            || "num_bits",
+                            self.num_bits,
+                            offset,
+                            || Value::known(F::from(num_bits as u64)),
+                        )?;
+                        table.assign_cell(
+                            || "value",
+                            self.value,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                        offset += 1;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
@@ -0,0 +1,414 @@
+//! What a circuit costs: its columns, gates, lookups and degree from the `ConstraintSystem`,
+//! and the rows it uses from a dry run of its synthesis.
+//!
+//! ```text
+//! $ cargo run --release --bin halo2-learn -- stats range-check1
+//! advice columns           1
+//! fixed columns            0 (0 lookup table columns)
+//! ...
+//! gates                    1
+//!   range check            1 constraint, degree 9
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use ff::Field;
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, FloorPlanner,
+        Fixed, Instance, Selector,
+    },
+};
+
+use crate::witness::Region;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateStats {
+    pub name: String,
+    pub constraints: usize,
+    /// The highest degree of its constraints, selector included.
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitStats {
+    pub advice_columns: usize,
+    /// Including the lookup table columns.
+    pub fixed_columns: usize,
+    pub lookup_table_columns: usize,
+    pub instance_columns: usize,
+    /// Before they are combined into fixed columns by the key generation.
+    pub selectors: usize,
+    /// Columns with equality enabled.
+    pub permutation_columns: usize,
+    /// Copy constraints, including the copies of constants and public inputs.
+    pub copies: usize,
+    pub gates: Vec<GateStats>,
+    pub lookups: usize,
+    /// The degree of the constraint system, i.e. the highest of the gates, lookups and
+    /// permutation argument.
+    pub degree: usize,
+    /// The largest rotation of any query, e.g. 1 for `Rotation::next()`.
+    pub max_rotation: usize,
+    /// Rows `0..rows` have at least one assigned cell or enabled selector, lookup tables
+    /// included.
+    pub rows: usize,
+    /// Rows of the largest lookup table.
+    pub table_rows: usize,
+    pub regions: usize,
+    /// Rows at the end that can't be assigned: the blinding factors and the last row.
+    pub blinding_rows: usize,
+    /// The smallest `k` with enough rows.
+    pub min_k: u32,
+}
+
+impl CircuitStats {
+    pub fn new<F: Field, C: Circuit<F>>(circuit: &C) -> Result<Self, Error> {
+        let (cs, dry_run) = DryRun::run(circuit)?;
+
+        let gates = cs
+            .gates()
+            .iter()
+            .map(|gate| GateStats {
+                name: gate.name().to_string(),
+                constraints: gate.polynomials().len(),
+                degree: gate.polynomials().iter().map(|p| p.degree()).max().unwrap_or(0),
+            })
+            .collect();
+
+        let rotations = cs
+            .advice_queries()
+            .iter()
+            .map(|(_, rotation)| rotation.0)
+            .chain(cs.fixed_queries().iter().map(|(_, rotation)| rotation.0))
+            .chain(cs.instance_queries().iter().map(|(_, rotation)| rotation.0));
+
+        Ok(Self {
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            lookup_table_columns: dry_run.table_columns.len(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            permutation_columns: cs.permutation().get_columns().len(),
+            copies: dry_run.copies,
+            gates,
+            lookups: cs.lookups().len(),
+            degree: cs.degree(),
+            max_rotation: rotations.map(i32::unsigned_abs).max().unwrap_or(0) as usize,
+            rows: dry_run.rows,
+            table_rows: dry_run.table_rows(),
+            regions: dry_run.regions.len(),
+            blinding_rows: blinding_rows(&cs),
+            min_k: fit_k(&cs, dry_run.rows),
+        })
+    }
+
+    pub fn constraints(&self) -> usize {
+        self.gates.iter().map(|gate| gate.constraints).sum()
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+
+        writeln!(f, "{:<24} {}", "advice columns", self.advice_columns)?;
+        writeln!(
+            f,
+            "{:<24} {} ({})",
+            "fixed columns",
+            self.fixed_columns,
+            plural(self.lookup_table_columns, "lookup table column")
+        )?;
+        writeln!(f, "{:<24} {}", "instance columns", self.instance_columns)?;
+        writeln!(f, "{:<24} {}", "selectors", self.selectors)?;
+        writeln!(f, "{:<24} {}", "permutation columns", self.permutation_columns)?;
+        writeln!(f, "{:<24} {}", "copy constraints", self.copies)?;
+        writeln!(f, "{:<24} {}", "gates", self.gates.len())?;
+        for gate in self.gates.iter() {
+            writeln!(
+                f,
+                "  {:<22} {}, degree {}",
+                gate.name,
+                plural(gate.constraints, "constraint"),
+                gate.degree
+            )?;
+        }
+        writeln!(f, "{:<24} {}", "lookups", self.lookups)?;
+        writeln!(f, "{:<24} {}", "degree", self.degree)?;
+        writeln!(f, "{:<24} {}", "max rotation", self.max_rotation)?;
+        writeln!(f, "{:<24} {} ({} in regions)", "rows", self.rows, plural(self.regions, "region"))?;
+        writeln!(f, "{:<24} {}", "lookup table rows", self.table_rows)?;
+        writeln!(f, "{:<24} {}", "blinding rows", self.blinding_rows)?;
+        write!(f, "{:<24} {}", "min k", self.min_k)
+    }
+}
+
+/// The smallest `k` whose usable rows fit every row assigned by `circuit`, lookup tables
+/// included, found by a dry run of its synthesis.
+///
+/// Below this `k`, `MockProver::run` and the key generation fail with
+/// `NotEnoughRowsAvailable`.
+pub fn min_k<F: Field, C: Circuit<F>>(circuit: &C) -> Result<u32, Error> {
+    let (cs, dry_run) = DryRun::run(circuit)?;
+    Ok(fit_k(&cs, dry_run.rows))
+}
+
+/// Rows at the end of the `2^k` that hold blinding factors, and the last row.
+pub(crate) fn blinding_rows<F: Field>(cs: &ConstraintSystem<F>) -> usize {
+    cs.blinding_factors() + 1
+}
+
+/// The smallest `k` with `rows` usable rows.
+pub(crate) fn fit_k<F: Field>(cs: &ConstraintSystem<F>, rows: usize) -> u32 {
+    let n = (rows + blinding_rows(cs)).max(cs.minimum_rows());
+    n.next_power_of_two().trailing_zeros()
+}
+
+// Records which rows are used, without evaluating any witness.
+#[derive(Default)]
+pub(crate) struct DryRun {
+    pub(crate) rows: usize,
+    pub(crate) regions: Vec<Region>,
+    pub(crate) copies: usize,
+    current_region: Option<Region>,
+    /// Fixed columns padded by the floor planner, i.e. lookup table columns.
+    pub(crate) table_columns: HashSet<Column<Fixed>>,
+    /// Number of rows assigned in each fixed column.
+    fixed_rows: HashMap<Column<Fixed>, usize>,
+}
+
+impl DryRun {
+    /// Configures and synthesizes `circuit` with unknown instances.
+    pub(crate) fn run<F: Field, C: Circuit<F>>(
+        circuit: &C,
+    ) -> Result<(ConstraintSystem<F>, Self), Error> {
+        let mut cs = ConstraintSystem::default();
+        let config = C::configure(&mut cs);
+
+        let mut dry_run = Self::default();
+        C::FloorPlanner::synthesize(&mut dry_run, circuit, config, cs.constants().clone())?;
+        Ok((cs, dry_run))
+    }
+
+    pub(crate) fn table_rows(&self) -> usize {
+        self.table_columns
+            .iter()
+            .filter_map(|column| self.fixed_rows.get(column))
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn use_row(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+        if let Some(region) = self.current_region.as_mut() {
+            if region.rows.is_empty() {
+                region.rows = row..row + 1;
+            } else {
+                region.rows = region.rows.start.min(row)..region.rows.end.max(row + 1);
+            }
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for DryRun {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_region = Some(Region {
+            name: name().into(),
+            rows: 0..0,
+        });
+    }
+
+    fn exit_region(&mut self) {
+        if let Some(region) = self.current_region.take() {
+            if !region.rows.is_empty() {
+                self.regions.push(region);
+            }
+        }
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let rows = self.fixed_rows.entry(column).or_default();
+        *rows = (*rows).max(row + 1);
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        self.copies += 1;
+        Ok(())
+    }
+
+    // Lookup tables are padded with their default value down to the last usable row;
+    // the padding does not count as used.
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.table_columns.insert(column);
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::{example1, example2, example3::FunctionCircuit};
+    use crate::range_check::{self, decompose_range_check::DecomposeRangeCheckCircuit};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use std::marker::PhantomData;
+
+    #[test]
+    fn test_fibonacci_stats() {
+        let stats = CircuitStats::new(&example1::MyCircuit::<Fp>(PhantomData)).unwrap();
+        assert_eq!(stats.advice_columns, 3);
+        assert_eq!(stats.fixed_columns, 0);
+        assert_eq!(stats.instance_columns, 1);
+        assert_eq!(stats.selectors, 1);
+        assert_eq!(stats.permutation_columns, 4);
+        // F[0] and F[1] from the instance column, 2 per next row, and the output
+        assert_eq!(stats.copies, 17);
+        assert_eq!(
+            stats.gates,
+            [GateStats {
+                name: "add".into(),
+                constraints: 1,
+                degree: 2
+            }]
+        );
+        assert_eq!(stats.lookups, 0);
+        assert_eq!(stats.max_rotation, 0);
+        assert_eq!((stats.rows, stats.regions), (8, 8));
+        assert_eq!(stats.min_k, 4);
+
+        // a single region, and the gate looks two rows ahead
+        let stats = CircuitStats::new(&example2::MyCircuit::<Fp>(PhantomData)).unwrap();
+        assert_eq!((stats.rows, stats.regions), (10, 1));
+        assert_eq!(stats.max_rotation, 2);
+        assert_eq!(stats.copies, 3);
+
+        let stats = CircuitStats::new(&FunctionCircuit::<Fp>::default()).unwrap();
+        assert_eq!(stats.advice_columns, 5);
+        assert_eq!(stats.gates.len(), 2);
+        assert_eq!(stats.rows, 1);
+    }
+
+    #[test]
+    fn test_range_check_stats() {
+        // the polynomial range check of 8 values is a degree 9 gate
+        let stats = CircuitStats::new(&range_check::example1::MyCircuit::<Fp, 8>::default()).unwrap();
+        assert_eq!(stats.gates[0].degree, 9);
+        assert_eq!(stats.degree, 9);
+        assert_eq!(stats.lookups, 0);
+
+        let stats =
+            CircuitStats::new(&range_check::example2::MyCircuit::<Fp, 8, 256>::default()).unwrap();
+        assert_eq!(stats.lookups, 1);
+        assert_eq!(stats.lookup_table_columns, 1);
+        assert_eq!((stats.rows, stats.table_rows), (256, 256));
+        assert_eq!(stats.min_k, 9);
+
+        let stats =
+            CircuitStats::new(&range_check::example3::MyCircuit::<Fp, 8, 256>::default()).unwrap();
+        assert_eq!(stats.lookup_table_columns, 2);
+        assert_eq!(stats.gates.len(), 0);
+
+        let stats = CircuitStats::new(&DecomposeRangeCheckCircuit::<Fp>::new(200, 8)).unwrap();
+        assert_eq!(stats.lookup_table_columns, 2);
+        assert_eq!(stats.max_rotation, 1);
+        assert_eq!(stats.regions, 1);
+        assert!(stats.table_rows > 4);
+    }
+
+    #[test]
+    fn test_min_k() {
+        // enough rows at min_k, but not one k below
+        let circuit = example1::MyCircuit::<Fp>(PhantomData);
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let k = min_k(&circuit).unwrap();
+        MockProver::run(k, &circuit, instances.clone()).unwrap().assert_satisfied();
+        assert!(matches!(
+            MockProver::run(k - 1, &circuit, instances),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+
+        let circuit = range_check::example2::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(200).into()),
+        };
+        let k = min_k(&circuit).unwrap();
+        MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
+        assert!(MockProver::run(k - 1, &circuit, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let stats = CircuitStats::new(&range_check::example1::MyCircuit::<Fp, 8>::default()).unwrap();
+        let report = stats.to_string();
+        assert!(report.contains("fixed columns            0 (0 lookup table columns)"));
+        assert!(report.contains("  range check            1 constraint, degree 9"));
+        assert!(report.ends_with(&format!("min k                    {}", stats.min_k)));
+    }
+}
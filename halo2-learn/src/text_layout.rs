@@ -0,0 +1,279 @@
+//! Prints the assigned matrix of a circuit as text, for terminals where the PNG layouts of
+//! `CircuitLayout` can't be looked at.
+//!
+//! ```text
+//!  row | i0 | a0 | a1 | a2 | q0 | region
+//! -----+----+----+----+----+----+-----------
+//!    0 |  1 |  1 |  1 |  2 |  1 | first row
+//! -----+----+----+----+----+----+-----------
+//!    1 |  1 |  1 |  2 |  3 |  1 | next row
+//! ```
+//!
+//! Columns are named by kind and index: `i` instance, `a` advice, `f` fixed (`t` for the
+//! fixed columns of lookup tables) and `q` for the selectors, in the order they are first
+//! enabled. `?` is an assigned cell whose value is unknown. The cells are captured by
+//! [`WitnessTable`], which also exports them to JSON and CSV.
+
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::plonk::{Any, Circuit, Column, Error};
+
+use crate::witness::{to_hex, WitnessTable};
+
+#[derive(Debug, Clone)]
+pub struct TextLayoutOptions {
+    /// Rows of each lookup table printed before the rest of the table is elided.
+    pub table_rows: usize,
+    pub show_copies: bool,
+    /// Copy constraints printed before the rest are elided.
+    pub max_copies: usize,
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        Self {
+            table_rows: 4,
+            show_copies: true,
+            max_copies: 32,
+        }
+    }
+}
+
+impl<F: PrimeField> WitnessTable<F> {
+    // Rows where only lookup table cells are assigned.
+    fn is_table_row(&self, columns: &[Column<Any>], row: usize) -> bool {
+        let mut assigned = columns
+            .iter()
+            .filter(|column| self.value(**column, row).is_some())
+            .peekable();
+        assigned.peek().is_some()
+            && assigned.all(|column| self.is_table_column(column))
+            && !self.selectors().iter().any(|s| self.is_enabled(s, row))
+    }
+
+    pub fn render(&self, options: &TextLayoutOptions) -> String {
+        let columns = self.columns();
+
+        let mut header = vec!["row".to_string()];
+        header.extend(columns.iter().map(|column| self.column_name(column)));
+        header.extend((0..self.selectors().len()).map(|i| format!("q{}", i)));
+        header.push("region".to_string());
+
+        // Each row is either cells, a separator before a new region, or an elision note.
+        enum Line {
+            Cells(Vec<String>),
+            Separator,
+            Note(String),
+        }
+        let mut lines = vec![Line::Cells(header), Line::Separator];
+
+        let mut table_run = 0;
+        for row in 0..self.num_rows() {
+            if self.is_table_row(&columns, row) {
+                table_run += 1;
+                if table_run > options.table_rows {
+                    continue;
+                }
+            } else {
+                if table_run > options.table_rows {
+                    lines.push(Line::Note(format!(
+                        "... {} more lookup table rows",
+                        table_run - options.table_rows
+                    )));
+                }
+                table_run = 0;
+            }
+
+            let regions: Vec<&str> = self
+                .regions()
+                .iter()
+                .filter(|region| region.rows.start == row)
+                .map(|region| region.name.as_str())
+                .collect();
+            if row > 0 && !regions.is_empty() {
+                lines.push(Line::Separator);
+            }
+
+            let mut cells = vec![row.to_string()];
+            cells.extend(columns.iter().map(|column| match self.value(*column, row) {
+                None => String::new(),
+                Some(None) => "?".to_string(),
+                Some(Some(value)) => format_value(&value),
+            }));
+            cells.extend(self.selectors().iter().map(|selector| {
+                if self.is_enabled(selector, row) { "1" } else { "" }.to_string()
+            }));
+            cells.push(regions.join(", "));
+            lines.push(Line::Cells(cells));
+        }
+        if table_run > options.table_rows {
+            lines.push(Line::Note(format!(
+                "... {} more lookup table rows",
+                table_run - options.table_rows
+            )));
+        }
+
+        let num_columns = columns.len() + self.selectors().len() + 2;
+        let mut widths = vec![0; num_columns];
+        for line in lines.iter() {
+            if let Line::Cells(cells) = line {
+                for (width, cell) in widths.iter_mut().zip(cells.iter()) {
+                    *width = (*width).max(cell.len());
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for line in lines {
+            let line = match line {
+                Line::Cells(cells) => {
+                    let last = cells.len() - 1;
+                    cells
+                        .iter()
+                        .zip(widths.iter())
+                        .enumerate()
+                        .map(|(i, (cell, width))| {
+                            // the region names are left-aligned, the values right-aligned
+                            if i == last {
+                                format!(" {}", cell)
+                            } else {
+                                format!(" {:>width$} ", cell, width = width)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("|")
+                }
+                Line::Separator => widths
+                    .iter()
+                    .map(|width| "-".repeat(width + 2))
+                    .collect::<Vec<_>>()
+                    .join("+"),
+                Line::Note(note) => format!(" {}", note),
+            };
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+
+        if options.show_copies && !self.copies().is_empty() {
+            out.push_str("\ncopy constraints:\n");
+            for (left, right) in self.copies().iter().take(options.max_copies) {
+                out.push_str(&format!(
+                    "  {}[{}] == {}[{}]\n",
+                    self.column_name(&left.0),
+                    left.1,
+                    self.column_name(&right.0),
+                    right.1
+                ));
+            }
+            if self.copies().len() > options.max_copies {
+                out.push_str(&format!("  ... {} more\n", self.copies().len() - options.max_copies));
+            }
+        }
+
+        out
+    }
+}
+
+impl<F: PrimeField> fmt::Display for WitnessTable<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&TextLayoutOptions::default()))
+    }
+}
+
+/// Synthesizes `circuit` and renders its assigned matrix.
+pub fn render_text<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+    options: &TextLayoutOptions,
+) -> Result<String, Error> {
+    Ok(WitnessTable::new(circuit, instances)?.render(options))
+}
+
+/// Small values in decimal, `-x` for `p - x`, and a shortened hex for the rest.
+/// Assumes a little-endian `Repr`, like the pasta fields.
+pub fn format_value<F: PrimeField>(value: &F) -> String {
+    fn small<F: PrimeField>(value: &F) -> Option<u64> {
+        let repr = value.to_repr();
+        let bytes = repr.as_ref();
+        if bytes[8..].iter().any(|b| *b != 0) {
+            return None;
+        }
+        Some(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+    }
+
+    if let Some(v) = small(value) {
+        v.to_string()
+    } else if let Some(v) = small(&-*value) {
+        format!("-{}", v)
+    } else {
+        let hex = to_hex(value);
+        format!("{}..{}", &hex[..6], &hex[hex.len() - 4..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example1;
+    use crate::range_check::example2;
+    use ff::Field;
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+    use std::marker::PhantomData;
+
+    fn cells(line: &str) -> Vec<&str> {
+        line.split('|').map(str::trim).collect()
+    }
+
+    #[test]
+    fn test_fibonacci_table() {
+        // col_a | col_b | col_c | selector, as drawn in `FiboChip::configure`
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let table = WitnessTable::new(&example1::MyCircuit(PhantomData), &instances).unwrap();
+        assert_eq!(table.num_rows(), 8);
+        assert_eq!(table.regions().len(), 8);
+
+        let text = table.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(cells(lines[0]), ["row", "i0", "a0", "a1", "a2", "q0", "region"]);
+        assert_eq!(cells(lines[2]), ["0", "1", "1", "1", "2", "1", "first row"]);
+        assert!(lines[3].starts_with("-----+"));
+        assert_eq!(cells(lines[4]), ["1", "1", "1", "2", "3", "1", "next row"]);
+        assert_eq!(cells(lines[6]), ["2", "55", "2", "3", "5", "1", "next row"]);
+        assert!(text.contains("copy constraints:\n  a0[0] == i0[0]\n"));
+        assert!(text.contains("a2[7] == i0[2]"));
+    }
+
+    #[test]
+    fn test_lookup_table_truncated() {
+        let circuit = example2::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(200).into()),
+        };
+        let text = render_text(&circuit, &[], &TextLayoutOptions::default()).unwrap();
+
+        // the two regions share rows 0 and 1 with the table, 4 more table rows are shown
+        let header = cells(text.lines().next().unwrap());
+        assert_eq!(header, ["row", "a0", "t0", "q0", "q1", "region"]);
+        assert!(text.contains("... 250 more lookup table rows"));
+        assert!(text.lines().count() < 20);
+    }
+
+    #[test]
+    fn test_unknown_values() {
+        let circuit = example2::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::unknown(),
+            lookup_value: Value::unknown(),
+        };
+        let table = WitnessTable::new(&circuit, &[]).unwrap();
+        assert!(table.to_string().contains('?'));
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&Fp::from(0)), "0");
+        assert_eq!(format_value(&Fp::from(55)), "55");
+        assert_eq!(format_value(&-Fp::from(3)), "-3");
+        assert_eq!(format_value(&Fp::from(2).pow_vartime([253])), "0x2000..0000");
+    }
+}
@@ -0,0 +1,524 @@
+//! Captures the cells assigned by a synthesis of any circuit, with their values, regions
+//! and annotations, and exports them to JSON or CSV so that runs can be diffed.
+//!
+//! ```text
+//! kind,column,row,value,region,annotation
+//! instance,0,0,0x0000..0001,,
+//! advice,0,0,0x0000..0001,entire fibonacci table,1
+//! selector,0,0,0x0000..0001,entire fibonacci table,
+//! ```
+//!
+//! Values are the full big-endian hex of the field element (shortened above), empty when
+//! unknown. Lookup table columns are of kind `table`, and selectors are numbered in the
+//! order they are first enabled. The records are sorted by kind, column and row, so two
+//! dumps of the same circuit only differ where the witness does.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, FloorPlanner,
+        Fixed, Instance, Selector,
+    },
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Region {
+    pub name: String,
+    pub rows: Range<usize>,
+}
+
+/// Where a cell was assigned, `None` outside of regions (instances and lookup tables).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellInfo {
+    pub region: Option<String>,
+    pub annotation: Option<String>,
+}
+
+/// One row of the exported witness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CellRecord {
+    /// `instance`, `advice`, `fixed`, `table` or `selector`.
+    pub kind: &'static str,
+    pub column: usize,
+    pub row: usize,
+    pub value: Option<String>,
+    pub region: Option<String>,
+    pub annotation: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WitnessDump<'a> {
+    rows: usize,
+    regions: &'a [Region],
+    cells: Vec<CellRecord>,
+    /// `[left, right]` as `a0[3]`, like the text layout.
+    copies: Vec<[String; 2]>,
+}
+
+/// The cells assigned by one synthesis of a circuit, with real witness values.
+#[derive(Debug, Clone)]
+pub struct WitnessTable<F: PrimeField> {
+    /// The value is `None` when the cell is assigned with an unknown value.
+    cells: HashMap<(Column<Any>, usize), (Option<F>, CellInfo)>,
+    selectors: Vec<Selector>,
+    enabled: HashMap<(Selector, usize), CellInfo>,
+    /// Fixed columns padded by the floor planner, i.e. lookup table columns.
+    table_columns: HashSet<Column<Fixed>>,
+    regions: Vec<Region>,
+    /// For each of `regions`, the number of regions entered before it, i.e. its index in
+    /// the failures of `MockProver`, which also counts the empty regions.
+    region_indices: Vec<usize>,
+    entered_regions: usize,
+    current_region: Option<Region>,
+    copies: Vec<((Column<Any>, usize), (Column<Any>, usize))>,
+    instances: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> WitnessTable<F> {
+    /// Synthesizes `circuit`, reading the instance columns from `instances`.
+    pub fn new<C: Circuit<F>>(circuit: &C, instances: &[Vec<F>]) -> Result<Self, Error> {
+        let mut cs = ConstraintSystem::default();
+        let config = C::configure(&mut cs);
+
+        let mut table = Self {
+            cells: HashMap::new(),
+            selectors: vec![],
+            enabled: HashMap::new(),
+            table_columns: HashSet::new(),
+            regions: vec![],
+            region_indices: vec![],
+            entered_regions: 0,
+            current_region: None,
+            copies: vec![],
+            instances: instances.to_vec(),
+        };
+        C::FloorPlanner::synthesize(&mut table, circuit, config, cs.constants().clone())?;
+
+        // Only the instance columns that are used show up.
+        let instance_columns: HashSet<Column<Any>> = table
+            .copies
+            .iter()
+            .flat_map(|(left, right)| [left.0, right.0])
+            .filter(|column| *column.column_type() == Any::Instance)
+            .collect();
+        for column in instance_columns {
+            for (row, value) in instances.get(column.index()).into_iter().flatten().enumerate() {
+                table
+                    .cells
+                    .insert((column, row), (Some(*value), CellInfo::default()));
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// The value of a cell: `None` if it is not assigned, `Some(None)` if its value is
+    /// unknown.
+    pub fn value(&self, column: impl Into<Column<Any>>, row: usize) -> Option<Option<F>> {
+        self.cells.get(&(column.into(), row)).map(|(value, _)| *value)
+    }
+
+    pub fn info(&self, column: impl Into<Column<Any>>, row: usize) -> Option<&CellInfo> {
+        self.cells.get(&(column.into(), row)).map(|(_, info)| info)
+    }
+
+    /// The selectors, in the order they are first enabled.
+    pub fn selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
+
+    pub fn is_enabled(&self, selector: &Selector, row: usize) -> bool {
+        self.enabled.contains_key(&(*selector, row))
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// The region with the given index in a `MockProver` failure, if it has any cells.
+    pub fn region_by_index(&self, index: usize) -> Option<&Region> {
+        let position = self.region_indices.iter().position(|i| *i == index)?;
+        Some(&self.regions[position])
+    }
+
+    pub fn copies(&self) -> &[((Column<Any>, usize), (Column<Any>, usize))] {
+        &self.copies
+    }
+
+    /// Number of rows with at least one assigned cell or enabled selector.
+    pub fn num_rows(&self) -> usize {
+        let cells = self.cells.keys().map(|(_, row)| row + 1);
+        let selectors = self.enabled.keys().map(|(_, row)| row + 1);
+        cells.chain(selectors).max().unwrap_or(0)
+    }
+
+    /// The assigned columns: instance, advice then fixed, each by index.
+    pub fn columns(&self) -> Vec<Column<Any>> {
+        let columns: HashSet<Column<Any>> = self.cells.keys().map(|(column, _)| *column).collect();
+        let mut columns: Vec<_> = columns.into_iter().collect();
+        columns.sort_by_key(|column| (kind_order(column.column_type()), column.index()));
+        columns
+    }
+
+    pub fn column_name(&self, column: &Column<Any>) -> String {
+        let prefix = match column.column_type() {
+            Any::Instance => "i",
+            Any::Advice => "a",
+            Any::Fixed if self.is_table_column(column) => "t",
+            Any::Fixed => "f",
+        };
+        format!("{}{}", prefix, column.index())
+    }
+
+    pub fn is_table_column(&self, column: &Column<Any>) -> bool {
+        self.table_columns
+            .iter()
+            .any(|table| Column::<Any>::from(*table) == *column)
+    }
+
+    /// Every assigned cell then every enabled selector, sorted by kind, column and row.
+    pub fn records(&self) -> Vec<CellRecord> {
+        let mut cells: Vec<_> = self.cells.iter().collect();
+        cells.sort_by_key(|((column, row), _)| {
+            (kind_order(column.column_type()), column.index(), *row)
+        });
+        let cells = cells.into_iter().map(|((column, row), (value, info))| {
+            let kind = match column.column_type() {
+                Any::Instance => "instance",
+                Any::Advice => "advice",
+                Any::Fixed if self.is_table_column(column) => "table",
+                Any::Fixed => "fixed",
+            };
+            CellRecord {
+                kind,
+                column: column.index(),
+                row: *row,
+                value: value.as_ref().map(to_hex),
+                region: info.region.clone(),
+                annotation: info.annotation.clone(),
+            }
+        });
+
+        let mut selectors: Vec<_> = self
+            .enabled
+            .iter()
+            .map(|((selector, row), info)| {
+                let index = self.selectors.iter().position(|s| s == selector).unwrap();
+                (index, *row, info)
+            })
+            .collect();
+        selectors.sort_by_key(|(index, row, _)| (*index, *row));
+        let selectors = selectors.into_iter().map(|(index, row, info)| CellRecord {
+            kind: "selector",
+            column: index,
+            row,
+            value: Some(to_hex(&F::ONE)),
+            region: info.region.clone(),
+            annotation: info.annotation.clone(),
+        });
+
+        cells.chain(selectors).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let copies = self
+            .copies
+            .iter()
+            .map(|(left, right)| {
+                [left, right].map(|(column, row)| format!("{}[{}]", self.column_name(column), row))
+            })
+            .collect();
+        let dump = WitnessDump {
+            rows: self.num_rows(),
+            regions: &self.regions,
+            cells: self.records(),
+            copies,
+        };
+        serde_json::to_string_pretty(&dump).expect("a witness dump always serializes")
+    }
+
+    /// One line per record, with the header `kind,column,row,value,region,annotation`.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("kind,column,row,value,region,annotation\n");
+        for record in self.records() {
+            let fields = [
+                record.kind.to_string(),
+                record.column.to_string(),
+                record.row.to_string(),
+                record.value.unwrap_or_default(),
+                csv_field(&record.region.unwrap_or_default()),
+                csv_field(&record.annotation.unwrap_or_default()),
+            ];
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    // `Selector::enable` annotates with an empty string.
+    fn info_here<A: Into<String>>(&self, annotation: A) -> CellInfo {
+        let annotation = annotation.into();
+        CellInfo {
+            region: self.current_region.as_ref().map(|region| region.name.clone()),
+            annotation: (!annotation.is_empty()).then_some(annotation),
+        }
+    }
+
+    fn set(&mut self, column: Column<Any>, row: usize, value: Value<F>, info: CellInfo) {
+        let mut known = None;
+        let _ = value.map(|value| known = Some(value));
+        self.cells.insert((column, row), (known, info));
+        self.use_row(row);
+    }
+
+    fn use_row(&mut self, row: usize) {
+        if let Some(region) = self.current_region.as_mut() {
+            if region.rows.is_empty() {
+                region.rows = row..row + 1;
+            } else {
+                region.rows = region.rows.start.min(row)..region.rows.end.max(row + 1);
+            }
+        }
+    }
+}
+
+fn kind_order(kind: &Any) -> u8 {
+    match kind {
+        Any::Instance => 0,
+        Any::Advice => 1,
+        Any::Fixed => 2,
+    }
+}
+
+/// `0x` and the big-endian hex of `value`. Assumes a little-endian `Repr`, like the pasta
+/// fields.
+pub fn to_hex<F: PrimeField>(value: &F) -> String {
+    let hex: String = value
+        .to_repr()
+        .as_ref()
+        .iter()
+        .rev()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("0x{}", hex)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl<F: PrimeField> Assignment<F> for WitnessTable<F> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_region = Some(Region {
+            name: name().into(),
+            rows: 0..0,
+        });
+    }
+
+    fn exit_region(&mut self) {
+        if let Some(region) = self.current_region.take() {
+            if !region.rows.is_empty() {
+                self.regions.push(region);
+                self.region_indices.push(self.entered_regions);
+            }
+            self.entered_regions += 1;
+        }
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if !self.selectors.contains(selector) {
+            self.selectors.push(*selector);
+        }
+        let info = self.info_here(annotation());
+        self.enabled.insert((*selector, row), info);
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.instances
+            .get(column.index())
+            .and_then(|column| column.get(row))
+            .map(|value| Value::known(*value))
+            .ok_or(Error::BoundsFailure)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let info = self.info_here(annotation());
+        self.set(column.into(), row, to().map(|v| v.into().evaluate()), info);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let info = self.info_here(annotation());
+        self.set(column.into(), row, to().map(|v| v.into().evaluate()), info);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.copies
+            .push(((left_column, left_row), (right_column, right_row)));
+        Ok(())
+    }
+
+    // Only lookup tables are padded this way; the padding is not recorded.
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.table_columns.insert(column);
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci::example2;
+    use crate::range_check::example2 as range_check;
+    use halo2_proofs::pasta::Fp;
+    use std::marker::PhantomData;
+
+    fn fibonacci(out: u64) -> WitnessTable<Fp> {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(out)]];
+        WitnessTable::new(&example2::MyCircuit(PhantomData), &instances).unwrap()
+    }
+
+    #[test]
+    fn test_records() {
+        let table = fibonacci(55);
+        let records = table.records();
+
+        // 3 instances, 10 advice cells and 8 enabled selectors
+        assert_eq!(records.len(), 21);
+        assert_eq!(records[0].kind, "instance");
+        assert_eq!(records[0].region, None);
+        assert_eq!(records[2].value, Some(to_hex(&Fp::from(55))));
+
+        let first = &records[3];
+        assert_eq!((first.kind, first.column, first.row), ("advice", 0, 0));
+        assert_eq!(first.region.as_deref(), Some("entire fibonacci table"));
+        assert_eq!(first.annotation.as_deref(), Some("1"));
+        assert_eq!(records[12].value, Some(to_hex(&Fp::from(55))));
+        assert!(records[13..].iter().all(|record| record.kind == "selector"));
+        assert_eq!(records[20].row, 7);
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value = serde_json::from_str(&fibonacci(55).to_json()).unwrap();
+        assert_eq!(json["rows"], 10);
+        assert_eq!(json["regions"][0]["name"], "entire fibonacci table");
+        assert_eq!(json["regions"][0]["rows"]["end"], 10);
+        assert_eq!(json["cells"][3]["annotation"], "1");
+        assert_eq!(json["copies"][0], serde_json::json!(["a0[0]", "i0[0]"]));
+        assert_eq!(json["copies"][2], serde_json::json!(["a0[9]", "i0[2]"]));
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = fibonacci(55).to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 22);
+        assert_eq!(lines[0], "kind,column,row,value,region,annotation");
+        assert_eq!(lines[6], format!("advice,0,2,{},entire fibonacci table,advice", to_hex(&Fp::from(2))));
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_diff_runs() {
+        // the same circuit with another public output only differs in that cell
+        let (a, b) = (fibonacci(55).to_csv(), fibonacci(56).to_csv());
+        let diff: Vec<_> = a.lines().zip(b.lines()).filter(|(a, b)| a != b).collect();
+        assert_eq!(diff.len(), 1);
+        assert!(diff[0].0.starts_with("instance,0,2,"));
+
+        let circuit = |value: u64| range_check::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(value).into()),
+        };
+        let a = WitnessTable::new(&circuit(200), &[]).unwrap().records();
+        let b = WitnessTable::new(&circuit(201), &[]).unwrap().records();
+        assert_eq!(a.len(), b.len());
+        let diff: Vec<_> = a.iter().zip(b.iter()).filter(|(a, b)| a != b).collect();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].1.region.as_deref(), Some("Assign value for lookup range check"));
+        // the table is exported too
+        assert_eq!(a.iter().filter(|record| record.kind == "table").count(), 256);
+    }
+
+    #[test]
+    fn test_unknown_values() {
+        let circuit = range_check::MyCircuit::<Fp, 8, 256> {
+            simple_value: Value::unknown(),
+            lookup_value: Value::unknown(),
+        };
+        let records = WitnessTable::new(&circuit, &[]).unwrap().records();
+        assert_eq!(records.iter().filter(|record| record.value.is_none()).count(), 2);
+    }
+}
@@ -0,0 +1,178 @@
+// Composes the exported chips from outside the crate, the way a downstream circuit would.
+
+use ff::PrimeField;
+use halo2_examples::prelude::*;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    pasta::Fp,
+    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+const RANGE: usize = 8; // 3-bit value
+const LOOKUP_RANGE: usize = 256; // 8-bit value
+
+#[derive(Debug, Clone)]
+struct ComposedConfig<F: PrimeField> {
+    fibo: FiboConfig,
+    range_check: LookupRangeCheckConfig<F, RANGE, LOOKUP_RANGE>,
+    function: FunctionConfig<F>,
+}
+
+/// Fibonacci (example1) + lookup range check (example2) + `f(a, b, c)` (example3),
+/// all laid out in the same circuit.
+#[derive(Default)]
+struct ComposedCircuit<F: PrimeField> {
+    value: Value<Assigned<F>>,
+    a: F,
+    b: F,
+    c: F,
+}
+
+impl<F: PrimeField> Circuit<F> for ComposedCircuit<F> {
+    type Config = ComposedConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let fibo = FiboChip::configure(meta, advice, instance);
+
+        // Share the first Fibonacci column with the range check.
+        let range_check = LookupRangeCheckConfig::configure(meta, advice[0]);
+        let function = FunctionChip::configure(meta);
+
+        ComposedConfig {
+            fibo,
+            range_check,
+            function,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::<F>::construct(config.fibo);
+        let (_, mut prev_b, mut prev_c) = chip.assign_first_row(layouter.namespace(|| "first row"))?;
+        for _i in 3..10 {
+            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+
+        config.range_check.table.load(&mut layouter)?;
+        config
+            .range_check
+            .assign_lookup(layouter.namespace(|| "lookup range check"), self.value)?;
+
+        FunctionChip::construct(config.function).assign(
+            layouter.namespace(|| "f(a, b, c)"),
+            self.a,
+            self.b,
+            self.c,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn compose_exported_chips() {
+    let k = 9;
+    let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+
+    let circuit = ComposedCircuit {
+        value: Value::known(Fp::from(200).into()),
+        a: Fp::from(10),
+        b: Fp::from(10),
+        c: Fp::from(15),
+    };
+    let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+    prover.assert_satisfied();
+
+    // 256 is outside of the 8-bit lookup table.
+    let circuit = ComposedCircuit {
+        value: Value::known(Fp::from(LOOKUP_RANGE as u64).into()),
+        a: Fp::from(10),
+        b: Fp::from(12),
+        c: Fp::from(15),
+    };
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[derive(Debug, Clone)]
+struct AssertZeroConfig<F: PrimeField> {
+    value: Column<Advice>,
+    selector: Selector,
+    is_zero: IsZeroConfig<F>,
+}
+
+/// Uses `IsZeroChip` inside a caller-defined gate: `value == 0` is witnessed
+/// through the chip's `value_inv` column and asserted to hold.
+#[derive(Default)]
+struct AssertZeroCircuit<F: PrimeField> {
+    value: F,
+}
+
+impl<F: PrimeField> Circuit<F> for AssertZeroCircuit<F> {
+    type Config = AssertZeroConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let value_inv = meta.advice_column();
+        let selector = meta.selector();
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(selector),
+            |meta| meta.query_advice(value, Rotation::cur()),
+            value_inv,
+        );
+
+        meta.create_gate("assert zero", |meta| {
+            let s = meta.query_selector(selector);
+            vec![s * (Expression::Constant(F::ONE) - is_zero.expr())]
+        });
+
+        AssertZeroConfig {
+            value,
+            selector,
+            is_zero,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = IsZeroChip::construct(config.is_zero);
+        layouter.assign_region(
+            || "assert zero",
+            |mut region| {
+                config.selector.enable(&mut region, 0)?;
+                region.assign_advice(|| "value", config.value, 0, || Value::known(self.value))?;
+                chip.assign(&mut region, 0, Value::known(self.value))
+            },
+        )
+    }
+}
+
+#[test]
+fn compose_is_zero_chip() {
+    let circuit = AssertZeroCircuit {
+        value: Fp::zero(),
+    };
+    MockProver::run(4, &circuit, vec![]).unwrap().assert_satisfied();
+
+    let circuit = AssertZeroCircuit {
+        value: Fp::from(7),
+    };
+    assert!(MockProver::run(4, &circuit, vec![]).unwrap().verify().is_err());
+}